@@ -16,6 +16,18 @@ pub enum Commands {
 
     // Extract data from a video
     Dislodge(DislodgeParams),
+
+    // Test an embedded video against simulated compression
+    Simulate(SimulateParams),
+
+    // Search for the densest settings that survive a degradation profile
+    Autotune(AutotuneParams),
+
+    // Read the title card of an embedded video
+    Inspect(InspectParams),
+
+    // Hide data inside an existing cover video
+    Hide(HideParams),
 }
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -28,6 +40,9 @@ pub enum EmbedPreset {
 
     // Fast Encoding and Small Size
     MaxEfficiency,
+
+    // Multi-Level Luma Encoding
+    Grayscale,
 }
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -35,6 +50,40 @@ pub enum EmbedOutputMode {
     Colored,
 
     Binary,
+
+    Quantized,
+
+    Grayscale,
+
+    #[value(name = "ycbcr")]
+    YCbCr,
+
+    Dct,
+
+    Ldpc,
+
+    Qr,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum OutputFormat {
+    // AVI video
+    Video,
+
+    // Printable PDF pages
+    Paper,
+
+    // Printable pages as PNG images
+    PaperPng,
+
+    // Single PNG image sized to the payload
+    Image,
+
+    // Animated GIF, binary, ldpc or 2-bit grayscale only
+    Gif,
+
+    // Lossless animated WebP
+    Webp,
 }
 
 impl From<EmbedOutputMode> for crate::settings::OutputMode {
@@ -42,6 +91,12 @@ impl From<EmbedOutputMode> for crate::settings::OutputMode {
         match mode {
             EmbedOutputMode::Colored => Self::Color,
             EmbedOutputMode::Binary => Self::Binary,
+            EmbedOutputMode::Quantized => Self::Quantized,
+            EmbedOutputMode::Grayscale => Self::Grayscale,
+            EmbedOutputMode::YCbCr => Self::YCbCr,
+            EmbedOutputMode::Dct => Self::Dct,
+            EmbedOutputMode::Ldpc => Self::Ldpc,
+            EmbedOutputMode::Qr => Self::Qr,
         }
     }
 }
@@ -68,6 +123,42 @@ pub struct EmbedParams {
 
     #[arg(long)]
     pub resolution: Option<String>,
+
+    #[arg(long)]
+    pub bits_per_channel: Option<u8>,
+
+    #[arg(long)]
+    pub fec: Option<u8>,
+
+    #[arg(long)]
+    pub header_interval: Option<u32>,
+
+    #[arg(long)]
+    pub interleave_depth: Option<u32>,
+
+    #[arg(long)]
+    pub saved_preset: Option<String>,
+
+    #[arg(long)]
+    pub region: Option<String>,
+
+    #[arg(long)]
+    pub overlay_image: Option<String>,
+
+    #[arg(long)]
+    pub overlay_text: Option<String>,
+
+    #[arg(long)]
+    pub title_card: bool,
+
+    #[arg(long)]
+    pub format: Option<OutputFormat>,
+
+    #[arg(long)]
+    pub audio: bool,
+
+    #[arg(long)]
+    pub subtitles: bool,
 }
 
 #[derive(Args, Default)]
@@ -83,4 +174,67 @@ pub struct DislodgeParams {
 
     #[arg(short, long)]
     pub out_path: Option<String>,
+
+    #[arg(long)]
+    pub capture: bool,
+
+    #[arg(long)]
+    pub scan: bool,
+
+    #[arg(short, long)]
+    pub key: Option<String>,
+}
+
+#[derive(Args, Default)]
+pub struct SimulateParams {
+    #[arg(short, long)]
+    pub in_path: Option<String>,
+
+    #[arg(short, long)]
+    pub profile: Option<String>,
+
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    #[arg(long)]
+    pub capture: bool,
+}
+
+#[derive(Args, Default)]
+pub struct AutotuneParams {
+    #[arg(short, long)]
+    pub in_path: Option<String>,
+
+    #[arg(short, long)]
+    pub profile: Option<String>,
+
+    #[arg(short, long)]
+    pub name: Option<String>,
+
+    #[arg(long)]
+    pub resolution: Option<String>,
+
+    #[arg(long)]
+    pub margin: Option<f64>,
+}
+
+#[derive(Args, Default)]
+pub struct InspectParams {
+    #[arg(short, long)]
+    pub in_path: Option<String>,
+}
+
+#[derive(Args, Default)]
+pub struct HideParams {
+    #[arg(short, long)]
+    pub in_path: Option<String>,
+
+    #[arg(short, long)]
+    pub cover_path: Option<String>,
+
+    #[arg(short, long)]
+    pub key: Option<String>,
+
+    #[arg(long)]
+    pub lossy: bool,
 }
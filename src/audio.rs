@@ -0,0 +1,343 @@
+use std::f64::consts::PI;
+use std::fs;
+
+use anyhow::anyhow;
+
+use crate::ethcer::{self, HEADER_WORDS};
+use crate::ffmpeg::{self, TempFile};
+use crate::hash;
+
+const SAMPLE_RATE: u32 = 44100;
+
+const BAUD: u32 = 300;
+
+const SAMPLES_PER_BIT: usize = (SAMPLE_RATE / BAUD) as usize;
+
+const MARK: f64 = 2400.0;
+
+const SPACE: f64 = 1200.0;
+
+const AMPLITUDE: f64 = 0.5;
+
+const PREAMBLE_BITS: usize = 32;
+
+const SYNC: u32 = 0x5656_4155;
+
+const OFFSETS: usize = 8;
+
+fn checksum(words: &[u32]) -> u32 {
+    let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+    hash::fnv1a_32(&bytes)
+}
+
+fn word_bits(word: u32) -> impl Iterator<Item = bool> {
+    (0..32).rev().map(move |i| (word >> i) & 1 == 1)
+}
+
+fn bits_word(bits: &[bool]) -> u32 {
+    bits.iter().fold(0, |word, bit| (word << 1) | *bit as u32)
+}
+
+fn packet(words: &[u32]) -> Vec<bool> {
+    let mut bits: Vec<bool> = (0..PREAMBLE_BITS).map(|i| i % 2 == 0).collect();
+
+    bits.extend(word_bits(SYNC));
+    for word in words {
+        bits.extend(word_bits(*word));
+    }
+    bits.extend(word_bits(checksum(words)));
+
+    bits
+}
+
+fn packet_bits() -> usize {
+    PREAMBLE_BITS + (HEADER_WORDS + 2) * 32
+}
+
+fn modulate(bits: &[bool]) -> Vec<i16> {
+    let mut samples = Vec::with_capacity(bits.len() * SAMPLES_PER_BIT);
+    let mut phase = 0.0;
+
+    for bit in bits {
+        let frequency = if *bit { MARK } else { SPACE };
+
+        for _ in 0..SAMPLES_PER_BIT {
+            phase += 2.0 * PI * frequency / SAMPLE_RATE as f64;
+            samples.push((phase.sin() * AMPLITUDE * i16::MAX as f64) as i16);
+        }
+    }
+
+    samples
+}
+
+fn energy(samples: &[f64], frequency: f64) -> f64 {
+    let coefficient = 2.0 * (2.0 * PI * frequency / SAMPLE_RATE as f64).cos();
+    let (mut previous, mut before) = (0.0, 0.0);
+
+    for sample in samples {
+        let current = sample + coefficient * previous - before;
+        before = previous;
+        previous = current;
+    }
+
+    previous * previous + before * before - coefficient * previous * before
+}
+
+fn demodulate(samples: &[f64], offset: usize) -> Vec<bool> {
+    samples[offset.min(samples.len())..]
+        .chunks_exact(SAMPLES_PER_BIT)
+        .map(|window| energy(window, MARK) > energy(window, SPACE))
+        .collect()
+}
+
+fn find_packets(bits: &[bool]) -> Vec<(usize, Vec<u32>)> {
+    let payload_bits = (HEADER_WORDS + 1) * 32;
+    let mut packets = Vec::new();
+    let mut index = 0;
+
+    while index + 32 + payload_bits <= bits.len() {
+        if bits_word(&bits[index..index + 32]) != SYNC {
+            index += 1;
+            continue;
+        }
+
+        let body = &bits[index + 32..index + 32 + payload_bits];
+        let words: Vec<u32> = body.chunks(32).map(bits_word).collect();
+
+        if checksum(&words[..HEADER_WORDS]) == words[HEADER_WORDS] {
+            packets.push((index, words[..HEADER_WORDS].to_vec()));
+            index += 32 + payload_bits;
+        } else {
+            index += 1;
+        }
+    }
+
+    packets
+}
+
+pub fn write_wav(path: &str, samples: &[i16]) -> anyhow::Result<()> {
+    let data_len = samples.len() as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+
+    fs::write(path, wav)?;
+    Ok(())
+}
+
+pub fn read_wav(path: &str) -> anyhow::Result<Vec<f64>> {
+    let bytes = fs::read(path)?;
+
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(anyhow!("Not a WAV file"));
+    }
+
+    let mut channels = 1;
+    let mut offset = 12;
+
+    while offset + 8 <= bytes.len() {
+        let tag = &bytes[offset..offset + 4];
+        let size = u32::from_le_bytes([
+            bytes[offset + 4],
+            bytes[offset + 5],
+            bytes[offset + 6],
+            bytes[offset + 7],
+        ]) as usize;
+        let body = &bytes[offset + 8..(offset + 8 + size).min(bytes.len())];
+
+        match tag {
+            b"fmt " if body.len() >= 16 => {
+                channels = u16::from_le_bytes([body[2], body[3]]).max(1) as usize;
+                let sample_rate = u32::from_le_bytes([body[4], body[5], body[6], body[7]]);
+                let sample_bits = u16::from_le_bytes([body[14], body[15]]);
+
+                if sample_rate != SAMPLE_RATE || sample_bits != 16 {
+                    return Err(anyhow!("WAV must be 16 bit PCM at {} Hz", SAMPLE_RATE));
+                }
+            }
+            b"data" => {
+                return Ok(body
+                    .chunks_exact(2 * channels)
+                    .map(|frame| {
+                        frame
+                            .chunks_exact(2)
+                            .map(|sample| i16::from_le_bytes([sample[0], sample[1]]) as f64)
+                            .sum::<f64>()
+                            / channels as f64
+                    })
+                    .collect());
+            }
+            _ => {}
+        }
+
+        offset += 8 + size + size % 2;
+    }
+
+    Err(anyhow!("WAV file has no data chunk"))
+}
+
+pub fn encode(words: &[u32], duration: f64) -> Vec<i16> {
+    let packet = packet(words);
+    let packet_seconds = packet.len() as f64 / BAUD as f64;
+    let repeats = ((duration / packet_seconds).floor() as usize).max(1);
+
+    modulate(&packet.repeat(repeats))
+}
+
+pub fn decode(samples: &[f64]) -> Vec<Vec<u32>> {
+    let mut found: Vec<(usize, Vec<u32>)> = Vec::new();
+    let packet_samples = packet_bits() * SAMPLES_PER_BIT;
+
+    for step in 0..OFFSETS {
+        let offset = step * SAMPLES_PER_BIT / OFFSETS;
+
+        for (index, words) in find_packets(&demodulate(samples, offset)) {
+            let position = offset + index * SAMPLES_PER_BIT;
+
+            if found
+                .iter()
+                .all(|(start, _)| start.abs_diff(position) > packet_samples / 2)
+            {
+                found.push((position, words));
+            }
+        }
+    }
+
+    found.into_iter().map(|(_, words)| words).collect()
+}
+
+pub fn mux(video_path: &str, words: &[u32], fps: f64) -> anyhow::Result<()> {
+    let duration = ethcer::video_duration(video_path, fps)?;
+
+    let wav_path = "output.wav";
+    write_wav(wav_path, &encode(words, duration))?;
+
+    let muxed_path = format!("{}.muxed.avi", video_path);
+    let muxed = ffmpeg::run(&[
+        "-i",
+        video_path,
+        "-i",
+        wav_path,
+        "-map",
+        "0:v",
+        "-map",
+        "1:a",
+        "-c:v",
+        "copy",
+        "-c:a",
+        "pcm_s16le",
+        &muxed_path,
+    ]);
+
+    if !muxed {
+        println!(
+            "Warning: ffmpeg could not mux the audio track, it was left at {}",
+            wav_path
+        );
+        return Ok(());
+    }
+
+    fs::rename(&muxed_path, video_path)?;
+    fs::remove_file(wav_path)?;
+    println!("Audio track muxed into {}", video_path);
+    Ok(())
+}
+
+pub fn read_headers(video_path: &str) -> Vec<Vec<u32>> {
+    if !ffmpeg::has_stream(video_path, "audio") {
+        return Vec::new();
+    }
+
+    let Ok(wav_file) = TempFile::new("wav") else {
+        return Vec::new();
+    };
+    let wav_path = wav_file.path();
+
+    let extracted = ffmpeg::run(&[
+        "-i",
+        video_path,
+        "-vn",
+        "-ac",
+        "1",
+        "-ar",
+        &SAMPLE_RATE.to_string(),
+        "-c:a",
+        "pcm_s16le",
+        "-f",
+        "wav",
+        &wav_path,
+    ]);
+
+    if !extracted {
+        return Vec::new();
+    }
+
+    let headers = match read_wav(&wav_path) {
+        Ok(samples) => decode(&samples),
+        Err(_) => Vec::new(),
+    };
+
+    if !headers.is_empty() {
+        println!("{} header copies read from the audio track", headers.len());
+    }
+
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> Vec<u32> {
+        (0..HEADER_WORDS as u32)
+            .map(|i| i.wrapping_mul(0x9E37_79B9))
+            .collect()
+    }
+
+    #[test]
+    fn decodes_every_packet_copy() {
+        let samples: Vec<f64> = encode(&header(), 4.0).iter().map(|s| *s as f64).collect();
+        let packets = decode(&samples);
+
+        assert!(packets.len() >= 2);
+        assert!(packets.iter().all(|words| *words == header()));
+    }
+
+    #[test]
+    fn decodes_with_offset_and_noise() {
+        let mut rng = crate::prng::Rng::new(5);
+        let mut samples = vec![0.0; 1234];
+        samples.extend(
+            encode(&header(), 2.0)
+                .iter()
+                .map(|s| *s as f64 + 4000.0 * rng.gaussian()),
+        );
+
+        assert!(decode(&samples).iter().any(|words| *words == header()));
+    }
+
+    #[test]
+    fn rejects_corrupted_packets() {
+        let mut bits = packet(&header());
+        let word = PREAMBLE_BITS + 32 + 5 * 32;
+        bits[word] = !bits[word];
+
+        assert!(find_packets(&bits).is_empty());
+        assert_eq!(find_packets(&packet(&header())).len(), 1);
+    }
+}
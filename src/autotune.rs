@@ -0,0 +1,127 @@
+use crate::ethcer;
+use crate::ldpc;
+use crate::settings::{OutputMode, Settings};
+use crate::simulate::{self, Degradation, Report};
+
+const FEC_RATIOS: [u8; 3] = [1, 3, 5];
+
+pub struct Candidate {
+    pub mode: OutputMode,
+
+    pub settings: Settings,
+}
+
+impl Candidate {
+    fn new(mode: OutputMode, size: i32, bits: u8, fec: u8, width: i32, height: i32) -> Self {
+        let mut settings = Settings::new(size, 8, 10, width, height);
+        settings.bits = bits;
+        settings.fec = fec;
+
+        Candidate { mode, settings }
+    }
+
+    pub fn density(&self) -> f64 {
+        let bits = ethcer::bits_per_frame(&self.mode, &self.settings) as f64;
+        let rate = match self.mode {
+            OutputMode::Ldpc => ldpc::DATA_BITS as f64 / ldpc::CODE_BITS as f64,
+            _ => 1.0,
+        };
+
+        bits * rate / self.settings.fec as f64
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{:?} mode, block size {}, {} bits, FEC x{}",
+            self.mode, self.settings.size, self.settings.bits, self.settings.fec
+        )
+    }
+}
+
+pub fn candidates(width: i32, height: i32) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+
+    for fec in FEC_RATIOS {
+        for size in 1..=8 {
+            candidates.push(Candidate::new(
+                OutputMode::Color,
+                size,
+                2,
+                fec,
+                width,
+                height,
+            ));
+            candidates.push(Candidate::new(
+                OutputMode::Binary,
+                size,
+                1,
+                fec,
+                width,
+                height,
+            ));
+            candidates.push(Candidate::new(
+                OutputMode::Ldpc,
+                size,
+                1,
+                fec,
+                width,
+                height,
+            ));
+
+            for bits in 1..=3 {
+                candidates.push(Candidate::new(
+                    OutputMode::Quantized,
+                    size,
+                    bits,
+                    fec,
+                    width,
+                    height,
+                ));
+                candidates.push(Candidate::new(
+                    OutputMode::YCbCr,
+                    size,
+                    bits,
+                    fec,
+                    width,
+                    height,
+                ));
+            }
+
+            for bits in 2..=3 {
+                candidates.push(Candidate::new(
+                    OutputMode::Grayscale,
+                    size,
+                    bits,
+                    fec,
+                    width,
+                    height,
+                ));
+            }
+        }
+
+        for size in [8, 16] {
+            candidates.push(Candidate::new(OutputMode::Dct, size, 2, fec, width, height));
+        }
+    }
+
+    candidates.sort_by(|a, b| b.density().total_cmp(&a.density()));
+    candidates
+}
+
+pub fn evaluate(
+    candidate: &Candidate,
+    payload: &[u8],
+    degradations: &[Degradation],
+    seed: u64,
+) -> anyhow::Result<Report> {
+    let data = ethcer::prepare_data(payload.to_vec(), candidate.mode, &candidate.settings)?;
+    let frames = ethcer::etch_frames(&data, &candidate.settings)?;
+
+    Ok(simulate::evaluate(
+        frames.into_iter(),
+        payload,
+        degradations,
+        seed,
+        false,
+    ))
+}
@@ -0,0 +1,85 @@
+use anyhow::anyhow;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn encode(data: &[u8]) -> String {
+    let mut text = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let value = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |v, (i, byte)| v | ((*byte as u32) << (16 - 8 * i)));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[((value >> (18 - 6 * i)) & 0x3F) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+
+    text
+}
+
+pub fn decode(text: &str) -> anyhow::Result<Vec<u8>> {
+    let text = text.trim().trim_end_matches('=');
+    let mut data = Vec::with_capacity(text.len() * 3 / 4);
+    let mut value = 0u32;
+    let mut bits = 0;
+
+    for symbol in text.bytes() {
+        let digit = ALPHABET
+            .iter()
+            .position(|&c| c == symbol)
+            .ok_or_else(|| anyhow!("Invalid base64 character '{}'", symbol as char))?;
+
+        value = (value << 6) | digit as u32;
+        bits += 6;
+
+        if bits >= 8 {
+            bits -= 8;
+            data.push((value >> bits) as u8);
+        }
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_rfc_4648_vectors() {
+        for (plain, encoded) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(encode(plain.as_bytes()), encoded);
+            assert_eq!(decode(encoded).unwrap(), plain.as_bytes());
+        }
+    }
+
+    #[test]
+    fn round_trips_every_byte_value() {
+        let data: Vec<u8> = (0..=255).collect();
+
+        for length in 0..data.len() {
+            assert_eq!(decode(&encode(&data[..length])).unwrap(), &data[..length]);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_characters() {
+        assert!(decode("Zm9v!").is_err());
+        assert!(decode("Zm 9v").is_err());
+        assert_eq!(decode(" Zm9v\n").unwrap(), b"foo");
+    }
+}
@@ -0,0 +1,454 @@
+use crate::ethcer::{etch_pixel, get_pixel};
+use crate::registration;
+use crate::settings::Settings;
+use crate::source::EmbedSource;
+
+const REGIONS: i32 = 4;
+
+const PALETTE_LEVELS: [u8; 5] = [0, 64, 128, 192, 255];
+
+pub struct ColorCorrection {
+    matrix: [[f64; 4]; 3],
+}
+
+pub struct Calibration {
+    width: i32,
+
+    height: i32,
+
+    regions: i32,
+
+    black: Vec<f64>,
+
+    white: Vec<f64>,
+
+    pub color: ColorCorrection,
+}
+
+pub fn luma(rgb: &[u8]) -> f64 {
+    0.299 * rgb[0] as f64 + 0.587 * rgb[1] as f64 + 0.114 * rgb[2] as f64
+}
+
+fn pattern_bit(block_x: i32, block_y: i32) -> bool {
+    let mut hash =
+        (block_x as u32).wrapping_mul(0x9E37_79B1) ^ (block_y as u32).wrapping_mul(0x85EB_CA77);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x2C1B_3C6D);
+    hash ^= hash >> 12;
+
+    hash & 1 == 1
+}
+
+fn palette_color(index: usize) -> [u8; 3] {
+    let levels = PALETTE_LEVELS.len();
+    let index = index % levels.pow(3);
+
+    [
+        PALETTE_LEVELS[index / (levels * levels)],
+        PALETTE_LEVELS[(index / levels) % levels],
+        PALETTE_LEVELS[index % levels],
+    ]
+}
+
+fn solve(mut a: [[f64; 4]; 4], mut b: [f64; 4]) -> Option<[f64; 4]> {
+    for col in 0..4 {
+        let pivot = (col..4).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+
+        if a[pivot][col].abs() < 1e-9 {
+            return None;
+        }
+
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        for row in 0..4 {
+            if row == col {
+                continue;
+            }
+
+            let factor = a[row][col] / a[col][col];
+            for k in col..4 {
+                a[row][k] -= factor * a[col][k];
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+
+    Some([
+        b[0] / a[0][0],
+        b[1] / a[1][1],
+        b[2] / a[2][2],
+        b[3] / a[3][3],
+    ])
+}
+
+impl ColorCorrection {
+    pub fn identity() -> Self {
+        ColorCorrection {
+            matrix: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+            ],
+        }
+    }
+
+    fn fit(observed: &[[f64; 3]], expected: &[[f64; 3]]) -> Option<Self> {
+        let mut normal = [[0.0; 4]; 4];
+        let mut targets = [[0.0; 4]; 3];
+
+        for (seen, wanted) in observed.iter().zip(expected) {
+            let input = [seen[0], seen[1], seen[2], 1.0];
+
+            for i in 0..4 {
+                for j in 0..4 {
+                    normal[i][j] += input[i] * input[j];
+                }
+
+                for channel in 0..3 {
+                    targets[channel][i] += input[i] * wanted[channel];
+                }
+            }
+        }
+
+        Some(ColorCorrection {
+            matrix: [
+                solve(normal, targets[0])?,
+                solve(normal, targets[1])?,
+                solve(normal, targets[2])?,
+            ],
+        })
+    }
+
+    fn from_samples(observed: &[[f64; 3]], expected: &[[f64; 3]]) -> Self {
+        match Self::fit(observed, expected) {
+            Some(correction) => correction,
+            None => {
+                println!("Warning: palette frame is unusable, colours will not be corrected");
+                Self::identity()
+            }
+        }
+    }
+
+    pub fn apply(&self, rgb: &[u8]) -> Vec<u8> {
+        self.matrix
+            .iter()
+            .map(|row| {
+                let value = row[0] * rgb[0] as f64
+                    + row[1] * rgb[1] as f64
+                    + row[2] * rgb[2] as f64
+                    + row[3];
+                value.round().clamp(0.0, 255.0) as u8
+            })
+            .collect()
+    }
+}
+
+impl Calibration {
+    pub fn uncalibrated(width: i32, height: i32) -> Self {
+        Calibration {
+            width,
+            height,
+            regions: 1,
+            black: vec![0.0],
+            white: vec![255.0],
+            color: ColorCorrection::identity(),
+        }
+    }
+
+    fn region(&self, x: i32, y: i32) -> usize {
+        let region_x = (x * self.regions / self.width).clamp(0, self.regions - 1);
+        let region_y = (y * self.regions / self.height).clamp(0, self.regions - 1);
+
+        (region_y * self.regions + region_x) as usize
+    }
+
+    pub fn threshold(&self, x: i32, y: i32) -> f64 {
+        let region = self.region(x, y);
+        (self.black[region] + self.white[region]) / 2.0
+    }
+
+    pub fn normalize(&self, x: i32, y: i32, luma: f64) -> f64 {
+        let region = self.region(x, y);
+        let span = (self.white[region] - self.black[region]).max(1.0);
+
+        (luma - self.black[region]) / span * 255.0
+    }
+
+    pub fn margin(&self) -> f64 {
+        self.black
+            .iter()
+            .zip(&self.white)
+            .map(|(black, white)| (white - black) / 2.0)
+            .fold(f64::MAX, f64::min)
+    }
+
+    pub fn report(&self) {
+        let black = self.black.iter().sum::<f64>() / self.black.len() as f64;
+        let white = self.white.iter().sum::<f64>() / self.white.len() as f64;
+
+        println!(
+            "Calibration measured black at {:.1} and white at {:.1} luma",
+            black, white
+        );
+        println!("Contrast margin: {:.1} luma", self.margin());
+
+        if self.margin() < 16.0 {
+            println!("Warning: contrast margin is very low, expect bit errors");
+        }
+    }
+}
+
+pub fn etch_calibration(settings: &Settings) -> anyhow::Result<EmbedSource> {
+    let region = registration::embed_area(settings.width, settings.height, settings.region);
+    let mut source = EmbedSource::new(settings.size, settings.width, settings.height, region);
+
+    let width = source.actual_size.width;
+    let height = source.actual_size.height;
+    let size = source.size;
+
+    for y in (0..height).step_by(size as usize) {
+        for x in (0..width).step_by(size as usize) {
+            let brightness = if pattern_bit(x / size, y / size) {
+                255
+            } else {
+                0
+            };
+            etch_pixel(&mut source, x, y, vec![brightness, brightness, brightness])?;
+        }
+    }
+
+    Ok(source)
+}
+
+pub fn read_calibration(source: &EmbedSource) -> anyhow::Result<Calibration> {
+    let width = source.actual_size.width;
+    let height = source.actual_size.height;
+    let size = source.size;
+
+    let mut calibration = Calibration {
+        width,
+        height,
+        regions: REGIONS,
+        black: vec![0.0; (REGIONS * REGIONS) as usize],
+        white: vec![0.0; (REGIONS * REGIONS) as usize],
+        color: ColorCorrection::identity(),
+    };
+
+    let uncalibrated = Calibration::uncalibrated(width, height);
+
+    let mut black_count = vec![0usize; calibration.black.len()];
+    let mut white_count = vec![0usize; calibration.white.len()];
+
+    for y in (0..height).step_by(size as usize) {
+        for x in (0..width).step_by(size as usize) {
+            let rgb = get_pixel(source, x, y, &uncalibrated);
+
+            let Some(rgb) = rgb else {
+                continue;
+            };
+
+            let region = calibration.region(x, y);
+
+            if pattern_bit(x / size, y / size) {
+                calibration.white[region] += luma(&rgb);
+                white_count[region] += 1;
+            } else {
+                calibration.black[region] += luma(&rgb);
+                black_count[region] += 1;
+            }
+        }
+    }
+
+    for region in 0..calibration.black.len() {
+        if black_count[region] == 0 || white_count[region] == 0 {
+            calibration.black[region] = 0.0;
+            calibration.white[region] = 255.0;
+            continue;
+        }
+
+        calibration.black[region] /= black_count[region] as f64;
+        calibration.white[region] /= white_count[region] as f64;
+    }
+
+    Ok(calibration)
+}
+
+pub fn etch_palette(settings: &Settings) -> anyhow::Result<EmbedSource> {
+    let region = registration::embed_area(settings.width, settings.height, settings.region);
+    let mut source = EmbedSource::new(settings.size, settings.width, settings.height, region);
+
+    let width = source.actual_size.width;
+    let height = source.actual_size.height;
+    let size = source.size as usize;
+
+    let mut index = 0;
+    for y in (0..height).step_by(size) {
+        for x in (0..width).step_by(size) {
+            etch_pixel(&mut source, x, y, palette_color(index).to_vec())?;
+            index += 1;
+        }
+    }
+
+    Ok(source)
+}
+
+pub fn read_palette(source: &EmbedSource) -> anyhow::Result<ColorCorrection> {
+    let width = source.actual_size.width;
+    let height = source.actual_size.height;
+    let size = source.size as usize;
+
+    let colors = PALETTE_LEVELS.len().pow(3);
+    let mut sums = vec![[0.0; 3]; colors];
+    let mut counts = vec![0usize; colors];
+
+    let uncalibrated = Calibration::uncalibrated(width, height);
+
+    let mut index = 0;
+    for y in (0..height).step_by(size) {
+        for x in (0..width).step_by(size) {
+            if let Some(rgb) = get_pixel(source, x, y, &uncalibrated) {
+                let color = index % colors;
+                for channel in 0..3 {
+                    sums[color][channel] += rgb[channel] as f64;
+                }
+                counts[color] += 1;
+            }
+            index += 1;
+        }
+    }
+
+    let mut observed = Vec::new();
+    let mut expected = Vec::new();
+
+    for color in 0..colors {
+        if counts[color] == 0 {
+            continue;
+        }
+
+        let count = counts[color] as f64;
+        observed.push([
+            sums[color][0] / count,
+            sums[color][1] / count,
+            sums[color][2] / count,
+        ]);
+
+        let palette = palette_color(color);
+        expected.push([palette[0] as f64, palette[1] as f64, palette[2] as f64]);
+    }
+
+    Ok(ColorCorrection::from_samples(&observed, &expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measured(black: Vec<f64>, white: Vec<f64>) -> Calibration {
+        Calibration {
+            width: 400,
+            height: 200,
+            regions: 2,
+            black,
+            white,
+            color: ColorCorrection::identity(),
+        }
+    }
+
+    #[test]
+    fn thresholds_follow_their_region() {
+        let calibration = measured(
+            vec![10.0, 40.0, 20.0, 0.0],
+            vec![200.0, 240.0, 230.0, 100.0],
+        );
+
+        assert_eq!(calibration.threshold(0, 0), 105.0);
+        assert_eq!(calibration.threshold(399, 0), 140.0);
+        assert_eq!(calibration.threshold(0, 199), 125.0);
+        assert_eq!(calibration.threshold(399, 199), 50.0);
+        assert_eq!(calibration.threshold(-5, 500), 125.0);
+        assert_eq!(calibration.margin(), 50.0);
+    }
+
+    #[test]
+    fn normalize_stretches_measured_levels() {
+        let calibration = measured(vec![16.0; 4], vec![235.0; 4]);
+
+        assert_eq!(calibration.normalize(0, 0, 16.0), 0.0);
+        assert_eq!(calibration.normalize(0, 0, 235.0), 255.0);
+        assert!((calibration.normalize(0, 0, 125.5) - 127.5).abs() < 1e-9);
+
+        let flat = Calibration {
+            regions: 1,
+            ..measured(vec![100.0], vec![100.0])
+        };
+        assert!(flat.normalize(0, 0, 101.0).is_finite());
+    }
+
+    #[test]
+    fn uncalibrated_is_the_full_range() {
+        let calibration = Calibration::uncalibrated(64, 64);
+
+        assert_eq!(calibration.threshold(10, 10), 127.5);
+        assert_eq!(calibration.normalize(10, 10, 200.0), 200.0);
+    }
+
+    fn cast(rgb: [f64; 3]) -> [f64; 3] {
+        [
+            0.8 * rgb[0] + 0.1 * rgb[1] + 12.0,
+            0.05 * rgb[0] + 0.85 * rgb[1] + 0.05 * rgb[2] + 6.0,
+            0.1 * rgb[1] + 0.7 * rgb[2] + 20.0,
+        ]
+    }
+
+    fn palette() -> Vec<[f64; 3]> {
+        (0..PALETTE_LEVELS.len().pow(3))
+            .map(|index| palette_color(index).map(|level| level as f64))
+            .collect()
+    }
+
+    #[test]
+    fn solve_recovers_a_known_system() {
+        let a = [
+            [4.0, 1.0, 0.0, 2.0],
+            [1.0, 3.0, 1.0, 0.0],
+            [0.0, 1.0, 5.0, 1.0],
+            [2.0, 0.0, 1.0, 6.0],
+        ];
+        let x = [1.0, -2.0, 0.5, 3.0];
+        let b = a.map(|row| row.iter().zip(&x).map(|(a, x)| a * x).sum());
+
+        let solved = solve(a, b).unwrap();
+        for (solved, x) in solved.iter().zip(&x) {
+            assert!((solved - x).abs() < 1e-9);
+        }
+
+        assert!(solve([[1.0, 2.0, 3.0, 4.0]; 4], [1.0; 4]).is_none());
+    }
+
+    #[test]
+    fn fit_undoes_a_colour_cast() {
+        let expected = palette();
+        let observed: Vec<[f64; 3]> = expected.iter().map(|rgb| cast(*rgb)).collect();
+        let correction = ColorCorrection::fit(&observed, &expected).unwrap();
+
+        for (seen, wanted) in observed.iter().zip(&expected) {
+            let seen = seen.map(|value| value.round().clamp(0.0, 255.0) as u8);
+            let corrected = correction.apply(&seen);
+
+            for (corrected, wanted) in corrected.iter().zip(wanted) {
+                assert!((*corrected as f64 - wanted).abs() <= 2.0);
+            }
+        }
+    }
+
+    #[test]
+    fn degenerate_palette_falls_back_to_identity() {
+        let grays: Vec<[f64; 3]> = [0.0, 128.0, 255.0].iter().map(|v| [*v; 3]).collect();
+        assert!(ColorCorrection::fit(&grays, &grays).is_none());
+        assert!(ColorCorrection::fit(&grays[..1], &grays[..1]).is_none());
+
+        let correction = ColorCorrection::from_samples(&grays[..1], &grays[..1]);
+        assert_eq!(correction.apply(&[10, 200, 30]), vec![10, 200, 30]);
+    }
+}
@@ -0,0 +1,294 @@
+use std::f64::consts::PI;
+
+pub const TILE: i32 = 8;
+
+pub const COEFFICIENTS: [(usize, usize); 4] = [(0, 1), (1, 0), (2, 0), (1, 1)];
+
+pub const AMPLITUDE: f64 = 140.0;
+
+fn scale(frequency: usize) -> f64 {
+    if frequency == 0 {
+        (1.0 / TILE as f64).sqrt()
+    } else {
+        (2.0 / TILE as f64).sqrt()
+    }
+}
+
+pub fn basis(u: usize, v: usize, x: usize, y: usize) -> f64 {
+    let n = TILE as f64;
+
+    scale(u)
+        * scale(v)
+        * ((2 * y + 1) as f64 * u as f64 * PI / (2.0 * n)).cos()
+        * ((2 * x + 1) as f64 * v as f64 * PI / (2.0 * n)).cos()
+}
+
+pub fn tile(bits: &[bool]) -> Vec<Vec<u8>> {
+    let mut tile = vec![vec![0u8; TILE as usize]; TILE as usize];
+
+    for (y, row) in tile.iter_mut().enumerate() {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let mut value = 128.0;
+
+            for (bit, (u, v)) in bits.iter().zip(COEFFICIENTS) {
+                let sign = if *bit { 1.0 } else { -1.0 };
+                value += sign * AMPLITUDE * basis(u, v, x, y);
+            }
+
+            *pixel = value.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+
+    tile
+}
+
+pub fn project(luma: &[Vec<f64>]) -> Vec<f64> {
+    COEFFICIENTS
+        .iter()
+        .map(|&(u, v)| {
+            let mut coefficient = 0.0;
+
+            for (y, row) in luma.iter().enumerate() {
+                for (x, value) in row.iter().enumerate() {
+                    coefficient += value * basis(u, v, x, y);
+                }
+            }
+
+            coefficient
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prng::Rng;
+
+    const LUMA_TABLE: [f64; 64] = [
+        16.0, 11.0, 10.0, 16.0, 24.0, 40.0, 51.0, 61.0, 12.0, 12.0, 14.0, 19.0, 26.0, 58.0, 60.0,
+        55.0, 14.0, 13.0, 16.0, 24.0, 40.0, 57.0, 69.0, 56.0, 14.0, 17.0, 22.0, 29.0, 51.0, 87.0,
+        80.0, 62.0, 18.0, 22.0, 37.0, 56.0, 68.0, 109.0, 103.0, 77.0, 24.0, 35.0, 55.0, 64.0, 81.0,
+        104.0, 113.0, 92.0, 49.0, 64.0, 78.0, 87.0, 103.0, 121.0, 120.0, 101.0, 72.0, 92.0, 95.0,
+        98.0, 112.0, 100.0, 103.0, 99.0,
+    ];
+
+    fn luma(tile: &[Vec<u8>]) -> Vec<Vec<f64>> {
+        tile.iter()
+            .map(|row| row.iter().map(|value| *value as f64).collect())
+            .collect()
+    }
+
+    fn jpeg(luma: &[Vec<f64>], quality: u32) -> Vec<Vec<f64>> {
+        let n = TILE as usize;
+        let scale = if quality < 50 {
+            5000.0 / quality as f64
+        } else {
+            200.0 - 2.0 * quality as f64
+        };
+
+        let mut quantized = vec![vec![0.0; n]; n];
+        for (u, row) in quantized.iter_mut().enumerate() {
+            for (v, coefficient) in row.iter_mut().enumerate() {
+                let mut sum = 0.0;
+                for (y, line) in luma.iter().enumerate() {
+                    for (x, value) in line.iter().enumerate() {
+                        sum += (value - 128.0) * basis(u, v, x, y);
+                    }
+                }
+
+                let step = ((LUMA_TABLE[u * n + v] * scale + 50.0) / 100.0)
+                    .floor()
+                    .clamp(1.0, 255.0);
+                *coefficient = (sum / step).round() * step;
+            }
+        }
+
+        (0..n)
+            .map(|y| {
+                (0..n)
+                    .map(|x| {
+                        let mut value = 128.0;
+                        for (u, row) in quantized.iter().enumerate() {
+                            for (v, coefficient) in row.iter().enumerate() {
+                                value += coefficient * basis(u, v, x, y);
+                            }
+                        }
+                        value.round().clamp(0.0, 255.0)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    const IMAGE: usize = 256;
+
+    type Image = Vec<Vec<f64>>;
+
+    fn block(image: &Image, top: usize, left: usize) -> Image {
+        let n = TILE as usize;
+        image[top..top + n]
+            .iter()
+            .map(|row| row[left..left + n].to_vec())
+            .collect()
+    }
+
+    fn compress(image: &Image, quality: u32, offset: usize) -> Image {
+        let n = TILE as usize;
+        let mut out = image.clone();
+        for top in (offset..IMAGE - n).step_by(n) {
+            for left in (offset..IMAGE - n).step_by(n) {
+                for (y, row) in jpeg(&block(image, top, left), quality).iter().enumerate() {
+                    out[top + y][left..left + n].copy_from_slice(row);
+                }
+            }
+        }
+        out
+    }
+
+    fn downscale(image: &Image, factor: usize) -> Image {
+        (0..IMAGE)
+            .map(|y| {
+                (0..IMAGE)
+                    .map(|x| {
+                        let (top, left) = (y / factor * factor, x / factor * factor);
+                        image[top..top + factor]
+                            .iter()
+                            .flat_map(|row| &row[left..left + factor])
+                            .sum::<f64>()
+                            / (factor * factor) as f64
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn noise(image: &Image, sigma: f64, rng: &mut Rng) -> Image {
+        image
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|value| (value + rng.gaussian() * sigma).round().clamp(0.0, 255.0))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn all_bits() -> impl Iterator<Item = Vec<bool>> {
+        (0..16u8).map(|pattern| (0..4).map(|i| (pattern >> i) & 1 == 1).collect())
+    }
+
+    #[test]
+    fn project_recovers_tile_bits() {
+        for bits in all_bits() {
+            let signs: Vec<bool> = project(&luma(&tile(&bits)))
+                .iter()
+                .map(|c| *c >= 0.0)
+                .collect();
+            assert_eq!(signs, bits);
+        }
+    }
+
+    #[test]
+    fn tile_survives_coarse_jpeg_quantization() {
+        for quality in [50, 20, 10, 5] {
+            for bits in all_bits() {
+                let compressed = jpeg(&luma(&tile(&bits)), quality);
+                let signs: Vec<bool> = project(&compressed).iter().map(|c| *c >= 0.0).collect();
+                assert_eq!(signs, bits, "quality {}", quality);
+            }
+        }
+    }
+
+    #[test]
+    fn error_rate_table() {
+        // Produces the binary vs dct table in the README, run with --nocapture to print it
+        let n = TILE as usize;
+        let tiles = IMAGE / n;
+        let mut rng = Rng::new(1);
+        let bits: Vec<bool> = (0..tiles * tiles * 4)
+            .map(|_| rng.next_u64() & 1 == 1)
+            .collect();
+
+        let mut binary = vec![vec![0.0; IMAGE]; IMAGE];
+        let mut signs = binary.clone();
+        for (index, chunk) in bits.chunks(4).enumerate() {
+            let (top, left) = (index / tiles * n, index % tiles * n);
+            for (y, row) in tile(chunk).iter().enumerate() {
+                for (x, value) in row.iter().enumerate() {
+                    binary[top + y][left + x] = if chunk[y / 4 * 2 + x / 4] { 255.0 } else { 0.0 };
+                    signs[top + y][left + x] = *value as f64;
+                }
+            }
+        }
+
+        let rows = [
+            ("`jpeg=20`", 1, Some(20), 0, 0.0),
+            ("`jpeg=10`", 1, Some(10), 0, 0.0),
+            ("`jpeg=5`", 1, Some(5), 0, 0.0),
+            ("`jpeg=20` on a grid shifted by 4 px", 1, Some(20), 4, 0.0),
+            ("`jpeg=10` on a grid shifted by 4 px", 1, Some(10), 4, 0.0),
+            ("`scale=0.5` then `jpeg=20`", 2, Some(20), 0, 0.0),
+            ("`scale=0.25` then `jpeg=20`", 4, Some(20), 0, 0.0),
+            ("`noise=64`", 1, None, 0, 64.0),
+            ("`noise=96`", 1, None, 0, 96.0),
+            ("`jpeg=30`, `noise=64`", 1, Some(30), 0, 64.0),
+        ];
+
+        println!("| Degradation | binary (block size 4) | dct (block size 8) |");
+        println!("|---|---|---|");
+        for (name, factor, quality, offset, sigma) in rows {
+            let mut degrade = |image: &Image| {
+                let mut image = downscale(image, factor);
+                if let Some(quality) = quality {
+                    image = compress(&image, quality, offset);
+                }
+                if sigma > 0.0 {
+                    image = noise(&image, sigma, &mut rng);
+                }
+                image
+            };
+            let (binary_image, signs_image) = (degrade(&binary), degrade(&signs));
+
+            // Tiles on the right and bottom edge fall partly outside the shifted grid
+            let (mut binary_errors, mut dct_errors, mut total) = (0, 0, 0);
+            for row in 0..tiles - 2 {
+                for column in 0..tiles - 2 {
+                    let chunk = &bits[(row * tiles + column) * 4..][..4];
+                    let (top, left) = (row * n, column * n);
+
+                    for (k, bit) in chunk.iter().enumerate() {
+                        let (y, x) = (top + k / 2 * 4, left + k % 2 * 4);
+                        let mean = (0..16)
+                            .map(|i| binary_image[y + i / 4][x + i % 4])
+                            .sum::<f64>()
+                            / 16.0;
+                        binary_errors += usize::from((mean >= 128.0) != *bit);
+                    }
+
+                    dct_errors += project(&block(&signs_image, top, left))
+                        .iter()
+                        .zip(chunk)
+                        .filter(|(coefficient, bit)| (**coefficient >= 0.0) != **bit)
+                        .count();
+                    total += 4;
+                }
+            }
+
+            let binary_rate = binary_errors as f64 / total as f64;
+            let dct_rate = dct_errors as f64 / total as f64;
+            println!(
+                "| {} | {:.2}% | {:.2}% |",
+                name,
+                binary_rate * 100.0,
+                dct_rate * 100.0
+            );
+
+            assert_eq!(binary_errors, 0, "{}", name);
+            if sigma > 0.0 {
+                assert!(dct_rate > 0.01, "{}", name);
+            } else if factor <= 2 {
+                assert_eq!(dct_errors, 0, "{}", name);
+            }
+        }
+    }
+}
@@ -2,13 +2,28 @@ use std::{fs, i32, thread, u32, vec};
 
 use anyhow::{Result as AnyhowResult, anyhow};
 
-use opencv::core::Mat;
+use opencv::core::{Mat, Size};
 use opencv::prelude::*;
-use opencv::videoio::{CAP_ANY, VideoCapture, VideoWriter};
-
+use opencv::videoio::{CAP_ANY, CAP_PROP_FRAME_COUNT, VideoCapture, VideoWriter};
+
+use crate::audio;
+use crate::calibration::{self, Calibration};
+use crate::dct;
+use crate::fec;
+use crate::golay;
+use crate::interleave;
+use crate::ldpc;
+use crate::overlay;
+use crate::qr;
+use crate::quantize;
+use crate::registration::{self, FrameKind, Tag};
 use crate::settings::{Data, OutputMode, Settings};
 use crate::source::EmbedSource;
+use crate::subtitles;
 use crate::timer::Timer;
+use crate::titlecard::{self, TitleCard};
+use crate::whiten;
+use crate::ycbcr;
 
 pub fn rip_bytes(path: &str) -> anyhow::Result<Vec<u8>> {
     let byte_data: Vec<u8> = fs::read(path)?;
@@ -42,28 +57,58 @@ pub fn rip_binary(byte_data: Vec<u8>) -> anyhow::Result<Vec<bool>> {
     Ok(binary_data)
 }
 
-pub fn rip_binary_u32(bytes: Vec<u32>) -> anyhow::Result<Vec<bool>> {
-    let byte_len = bytes.len();
-    let mut binary_data: Vec<bool> = Vec::new();
-    for byte in &bytes {
-        let mut bits: String = format!("{:b}", byte);
-        let missing_0 = 32 - bits.len();
+pub fn prepare_data(
+    bytes: Vec<u8>,
+    out_mode: OutputMode,
+    settings: &Settings,
+) -> anyhow::Result<Data> {
+    if let OutputMode::Qr = out_mode {
+        return Ok(Data::from_qr(bytes));
+    }
 
-        for _ in 0..missing_0 {
-            bits.insert(0, '0');
+    let bytes = fec::encode(&bytes, settings.fec);
+    let depth = interleave_depth(&out_mode, settings, bytes.len());
+    let bytes = interleave::interleave(&bytes, depth);
+    let bytes = whiten::scramble(&bytes, settings.seed as u64);
+
+    Ok(match out_mode {
+        OutputMode::Color => Data::from_color(bytes),
+        OutputMode::Binary => Data::from_binary(rip_binary(bytes)?),
+        OutputMode::Quantized => Data::from_quantized(rip_binary(bytes)?),
+        OutputMode::Grayscale => Data::from_grayscale(rip_binary(bytes)?),
+        OutputMode::YCbCr => Data::from_ycbcr(rip_binary(bytes)?),
+        OutputMode::Dct => Data::from_dct(rip_binary(bytes)?),
+        OutputMode::Ldpc => {
+            let length = bytes.len();
+            Data::from_ldpc(ldpc::encode(&rip_binary(bytes)?), length)
         }
+        OutputMode::Qr => unreachable!(),
+    })
+}
 
-        for bit in bits.chars() {
-            binary_data.push(bit == '1');
-        }
+pub fn interleave_depth(out_mode: &OutputMode, settings: &Settings, length: usize) -> usize {
+    if settings.interleave_depth > 0 {
+        return settings.interleave_depth as usize;
     }
 
-    println!("Binary Ripped Successfully");
-    println!("Converted {} bytes to {} bits", byte_len, binary_data.len());
-    Ok(binary_data)
+    (bits_per_frame(out_mode, settings) / 8).clamp(1, length.max(1))
 }
 
-fn translate_u8(binary_data: Vec<bool>) -> anyhow::Result<Vec<u8>> {
+pub fn rip_bits(bytes: &[u8]) -> Vec<bool> {
+    bytes
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1 == 1))
+        .collect()
+}
+
+fn rip_bits_u32(words: &[u32]) -> Vec<bool> {
+    words
+        .iter()
+        .flat_map(|word| (0..32).rev().map(move |i| (word >> i) & 1 == 1))
+        .collect()
+}
+
+pub fn translate_u8(binary_data: Vec<bool>) -> anyhow::Result<Vec<u8>> {
     let mut buffer: Vec<bool> = Vec::new();
     let mut byte_data: Vec<u8> = Vec::new();
 
@@ -105,7 +150,12 @@ pub fn write_bytes(path: &str, data: Vec<u8>) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn get_pixel(frame: &EmbedSource, x: i32, y: i32) -> Option<Vec<u8>> {
+pub fn get_pixel(
+    frame: &EmbedSource,
+    x: i32,
+    y: i32,
+    calibration: &Calibration,
+) -> Option<Vec<u8>> {
     let mut r_list: Vec<u8> = Vec::new();
     let mut g_list: Vec<u8> = Vec::new();
     let mut b_list: Vec<u8> = Vec::new();
@@ -127,10 +177,14 @@ fn get_pixel(frame: &EmbedSource, x: i32, y: i32) -> Option<Vec<u8>> {
     let g_avg = g_list.iter().map(|&x| x as usize).sum::<usize>() / g_list.len();
     let b_avg = b_list.iter().map(|&x| x as usize).sum::<usize>() / b_list.len();
 
-    Some(vec![r_avg as u8, g_avg as u8, b_avg as u8])
+    Some(
+        calibration
+            .color
+            .apply(&[r_avg as u8, g_avg as u8, b_avg as u8]),
+    )
 }
 
-fn etch_pixel(frame: &mut EmbedSource, x: i32, y: i32, rgb: Vec<u8>) -> anyhow::Result<()> {
+pub fn etch_pixel(frame: &mut EmbedSource, x: i32, y: i32, rgb: Vec<u8>) -> anyhow::Result<()> {
     for i in 0..frame.size {
         for j in 0..frame.size {
             let bgr = frame
@@ -161,13 +215,15 @@ fn etch_color(
         for x in (0..widht).step_by(size) {
             let local_idx = global_index.clone();
 
-            let rgb = vec![data[local_idx], data[local_idx + 1], data[local_idx + 2]];
+            let rgb: Vec<u8> = (0..3)
+                .map(|i| data.get(local_idx + i).copied().unwrap_or(0))
+                .collect();
 
             etch_pixel(source, x, y, rgb).unwrap();
             *global_index += 3;
 
-            if *global_index + 2 >= data.len() {
-                return Err(anyhow!("Index Beyond Data"));
+            if *global_index >= data.len() {
+                return Ok(());
             }
         }
     }
@@ -196,7 +252,176 @@ fn etch_bw(
             etch_pixel(source, x, y, rgb).unwrap();
             *global_index += 1;
             if *global_index >= data.len() {
-                return Err(anyhow!("Index Beyond Data"));
+                return Ok(());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn etch_quantized(
+    source: &mut EmbedSource,
+    data: &Vec<bool>,
+    global_index: &mut usize,
+    bits: u8,
+) -> anyhow::Result<()> {
+    let _timer = Timer::new("Etching frame");
+
+    let widht = source.actual_size.width;
+    let height = source.actual_size.height;
+    let size = source.size as usize;
+
+    let levels = quantize::levels(bits);
+    let bits = bits as usize;
+
+    for y in (0..height).step_by(size) {
+        for x in (0..widht).step_by(size) {
+            let mut rgb = Vec::new();
+
+            for channel in 0..3 {
+                let start = *global_index + channel * bits;
+                let mut symbol = vec![false; bits];
+
+                for (i, bit) in symbol.iter_mut().enumerate() {
+                    *bit = data.get(start + i).copied().unwrap_or(false);
+                }
+
+                rgb.push(quantize::bits_to_level(&symbol, &levels));
+            }
+
+            etch_pixel(source, x, y, rgb).unwrap();
+            *global_index += 3 * bits;
+
+            if *global_index >= data.len() {
+                return Ok(());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn etch_grayscale(
+    source: &mut EmbedSource,
+    data: &Vec<bool>,
+    global_index: &mut usize,
+    bits: u8,
+) -> anyhow::Result<()> {
+    let _timer = Timer::new("Etching frame");
+
+    let widht = source.actual_size.width;
+    let height = source.actual_size.height;
+    let size = source.size as usize;
+
+    let levels = quantize::levels(bits);
+    let bits = bits as usize;
+
+    for y in (0..height).step_by(size) {
+        for x in (0..widht).step_by(size) {
+            let mut symbol = vec![false; bits];
+
+            for (i, bit) in symbol.iter_mut().enumerate() {
+                *bit = data.get(*global_index + i).copied().unwrap_or(false);
+            }
+
+            let brightness = quantize::bits_to_level(&symbol, &levels);
+
+            etch_pixel(source, x, y, vec![brightness, brightness, brightness]).unwrap();
+            *global_index += bits;
+
+            if *global_index >= data.len() {
+                return Ok(());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn etch_ycbcr(
+    source: &mut EmbedSource,
+    data: &Vec<bool>,
+    global_index: &mut usize,
+    bits: u8,
+) -> anyhow::Result<()> {
+    let _timer = Timer::new("Etching frame");
+
+    let size = source.size;
+    let cell = (size * 2) as usize;
+    let widht = source.actual_size.width / (size * 2) * (size * 2);
+    let height = source.actual_size.height / (size * 2) * (size * 2);
+
+    let levels = quantize::levels_between(bits, ycbcr::LUMA_LOW, ycbcr::LUMA_HIGH);
+    let bits = bits as usize;
+
+    let bit = |index: usize| data.get(index).copied().unwrap_or(false);
+
+    for y in (0..height).step_by(cell) {
+        for x in (0..widht).step_by(cell) {
+            let cb = ycbcr::chroma(bit(*global_index + 4 * bits));
+            let cr = ycbcr::chroma(bit(*global_index + 4 * bits + 1));
+
+            for block in 0..4 {
+                let symbol: Vec<bool> = (0..bits)
+                    .map(|i| bit(*global_index + block * bits + i))
+                    .collect();
+                let luma = quantize::bits_to_level(&symbol, &levels);
+
+                let block_x = x + (block as i32 % 2) * size;
+                let block_y = y + (block as i32 / 2) * size;
+
+                etch_pixel(source, block_x, block_y, ycbcr::to_rgb(luma, cb, cr)).unwrap();
+            }
+
+            *global_index += 4 * bits + 2;
+
+            if *global_index >= data.len() {
+                return Ok(());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn etch_dct(
+    source: &mut EmbedSource,
+    data: &Vec<bool>,
+    global_index: &mut usize,
+) -> anyhow::Result<()> {
+    let _timer = Timer::new("Etching frame");
+
+    let widht = source.actual_size.width;
+    let height = source.actual_size.height;
+    let size = source.size as usize;
+    let bits = dct::COEFFICIENTS.len();
+
+    for y in (0..height).step_by(size) {
+        for x in (0..widht).step_by(size) {
+            let symbol: Vec<bool> = (0..bits)
+                .map(|i| data.get(*global_index + i).copied().unwrap_or(false))
+                .collect();
+            let tile = dct::tile(&symbol);
+
+            for i in 0..source.size {
+                for j in 0..source.size {
+                    let brightness = tile[(i % dct::TILE) as usize][(j % dct::TILE) as usize];
+                    let bgr = source
+                        .image
+                        .at_2d_mut::<opencv::core::Vec3b>(y + i, x + j)
+                        .unwrap();
+
+                    bgr[2] = brightness;
+                    bgr[1] = brightness;
+                    bgr[0] = brightness;
+                }
+            }
+
+            *global_index += bits;
+
+            if *global_index >= data.len() {
+                return Ok(());
             }
         }
     }
@@ -206,6 +431,7 @@ fn etch_bw(
 
 fn read_bw(
     source: &EmbedSource,
+    calibration: &Calibration,
     current_frame: i32,
     final_frame: i32,
     final_bit: i32,
@@ -218,14 +444,14 @@ fn read_bw(
 
     for y in (0..height).step_by(size) {
         for x in (0..width).step_by(size) {
-            let rgb = get_pixel(&source, x, y);
+            let rgb = get_pixel(&source, x, y, calibration);
 
             if rgb.is_none() {
                 continue;
             } else {
                 let rgb = rgb.unwrap();
 
-                if rgb[0] >= 127 {
+                if calibration::luma(&rgb) >= calibration.threshold(x, y) {
                     binary_data.push(true);
                 } else {
                     binary_data.push(false);
@@ -235,15 +461,46 @@ fn read_bw(
     }
 
     if current_frame == final_frame {
-        let slice = binary_data[0..final_bit as usize].to_vec();
-        return Ok(slice);
+        binary_data.truncate(final_bit as usize);
     }
 
     Ok(binary_data)
 }
 
+fn read_soft(
+    source: &EmbedSource,
+    calibration: &Calibration,
+    current_frame: i32,
+    final_frame: i32,
+    final_bit: i32,
+) -> anyhow::Result<Vec<f64>> {
+    let width: i32 = source.actual_size.width;
+    let height: i32 = source.actual_size.height;
+
+    let size = source.size as usize;
+    let mut soft_data: Vec<f64> = Vec::new();
+
+    for y in (0..height).step_by(size) {
+        for x in (0..width).step_by(size) {
+            let Some(rgb) = get_pixel(&source, x, y, calibration) else {
+                continue;
+            };
+
+            let level = calibration.normalize(x, y, calibration::luma(&rgb));
+            soft_data.push(((level - 127.5) / 127.5).clamp(-1.0, 1.0));
+        }
+    }
+
+    if current_frame == final_frame {
+        soft_data.truncate(final_bit as usize);
+    }
+
+    Ok(soft_data)
+}
+
 fn read_color(
     source: &EmbedSource,
+    calibration: &Calibration,
     current_frame: i32,
     final_frame: i32,
     final_bit: i32,
@@ -256,7 +513,7 @@ fn read_color(
 
     for y in (0..height).step_by(size) {
         for x in (0..width).step_by(size) {
-            let rgb = get_pixel(&source, x, y);
+            let rgb = get_pixel(&source, x, y, calibration);
 
             if rgb.is_none() {
                 continue;
@@ -270,183 +527,523 @@ fn read_color(
     }
 
     if current_frame == final_frame {
-        let slice = byte_data[0..final_bit as usize].to_vec();
-        return Ok(slice);
+        byte_data.truncate(final_bit as usize);
     }
 
     Ok(byte_data)
 }
 
-fn etch_instructions(settings: &Settings, data: &Data) -> anyhow::Result<EmbedSource> {
-    let instruction_size = 5;
+fn read_quantized(
+    source: &EmbedSource,
+    calibration: &Calibration,
+    current_frame: i32,
+    final_frame: i32,
+    final_bit: i32,
+    bits: u8,
+) -> anyhow::Result<Vec<bool>> {
+    let width: i32 = source.actual_size.width;
+    let height: i32 = source.actual_size.height;
 
-    let mut u32_instructions: Vec<u32> = Vec::new();
+    let size = source.size as usize;
+    let levels = quantize::levels(bits);
+    let mut binary_data: Vec<bool> = Vec::new();
 
-    let frame_size = (settings.width * settings.height) as usize;
+    for y in (0..height).step_by(size) {
+        for x in (0..width).step_by(size) {
+            let rgb = get_pixel(&source, x, y, calibration);
 
-    match data.out_mode {
-        OutputMode::Color => {
-            u32_instructions.push(u32::MAX);
+            if let Some(rgb) = rgb {
+                for value in rgb {
+                    binary_data.extend(quantize::level_to_bits(value as f64, &levels));
+                }
+            }
+        }
+    }
+
+    if current_frame == final_frame {
+        binary_data.truncate(final_bit as usize);
+    }
+
+    Ok(binary_data)
+}
+
+fn read_grayscale(
+    source: &EmbedSource,
+    calibration: &Calibration,
+    current_frame: i32,
+    final_frame: i32,
+    final_bit: i32,
+    bits: u8,
+) -> anyhow::Result<Vec<bool>> {
+    let width: i32 = source.actual_size.width;
+    let height: i32 = source.actual_size.height;
+
+    let size = source.size as usize;
+    let levels = quantize::levels(bits);
+    let mut binary_data: Vec<bool> = Vec::new();
+
+    for y in (0..height).step_by(size) {
+        for x in (0..width).step_by(size) {
+            let rgb = get_pixel(&source, x, y, calibration);
 
-            let frame_data_size = frame_size / settings.size.pow(2) as usize;
-            let final_byte = data.bytes.len() % frame_data_size;
-            let mut final_frame = data.bytes.len() / frame_data_size;
+            if let Some(rgb) = rgb {
+                let luma = calibration.normalize(x, y, calibration::luma(&rgb));
+                binary_data.extend(quantize::level_to_bits(luma, &levels));
+            }
+        }
+    }
 
-            if data.bytes.len() % frame_size != 0 {
-                final_frame += 1;
+    if current_frame == final_frame {
+        binary_data.truncate(final_bit as usize);
+    }
+
+    Ok(binary_data)
+}
+
+fn read_ycbcr(
+    source: &EmbedSource,
+    calibration: &Calibration,
+    current_frame: i32,
+    final_frame: i32,
+    final_bit: i32,
+    bits: u8,
+) -> anyhow::Result<Vec<bool>> {
+    let size = source.size;
+    let cell = (size * 2) as usize;
+    let width = source.actual_size.width / (size * 2) * (size * 2);
+    let height = source.actual_size.height / (size * 2) * (size * 2);
+
+    let levels = quantize::levels_between(bits, ycbcr::LUMA_LOW, ycbcr::LUMA_HIGH);
+    let mut binary_data: Vec<bool> = Vec::new();
+
+    for y in (0..height).step_by(cell) {
+        for x in (0..width).step_by(cell) {
+            let mut average = [0.0; 3];
+
+            for block in 0..4 {
+                let block_x = x + (block % 2) * size;
+                let block_y = y + (block / 2) * size;
+
+                let rgb = get_pixel(&source, block_x, block_y, calibration)
+                    .expect("Failed to read block");
+                let rgb = [rgb[0] as f64, rgb[1] as f64, rgb[2] as f64];
+
+                let (luma, _, _) = ycbcr::from_rgb(&rgb);
+                binary_data.extend(quantize::level_to_bits(luma, &levels));
+
+                for channel in 0..3 {
+                    average[channel] += rgb[channel] / 4.0;
+                }
             }
 
-            dbg!(final_frame);
-            u32_instructions.push(final_frame as u32);
-            u32_instructions.push(final_byte as u32);
+            let (_, cb, cr) = ycbcr::from_rgb(&average);
+            binary_data.push(cb >= 128.0);
+            binary_data.push(cr >= 128.0);
         }
-        OutputMode::Binary => {
-            u32_instructions.push(u32::MIN);
+    }
 
-            let frame_data_size = frame_size / settings.size.pow(2) as usize;
-            let final_byte = data.binary.len() % frame_data_size;
-            let mut final_frame = data.binary.len() / frame_data_size;
+    if current_frame == final_frame {
+        binary_data.truncate(final_bit as usize);
+    }
+
+    Ok(binary_data)
+}
+
+fn read_dct(
+    source: &EmbedSource,
+    current_frame: i32,
+    final_frame: i32,
+    final_bit: i32,
+) -> anyhow::Result<Vec<bool>> {
+    let width: i32 = source.actual_size.width;
+    let height: i32 = source.actual_size.height;
 
-            if data.binary.len() % frame_size != 0 {
-                final_frame += 1;
+    let size = source.size as usize;
+    let mut binary_data: Vec<bool> = Vec::new();
+
+    for y in (0..height).step_by(size) {
+        for x in (0..width).step_by(size) {
+            let mut coefficients = vec![0.0; dct::COEFFICIENTS.len()];
+
+            for tile_y in (0..source.size).step_by(dct::TILE as usize) {
+                for tile_x in (0..source.size).step_by(dct::TILE as usize) {
+                    let mut luma = vec![vec![0.0; dct::TILE as usize]; dct::TILE as usize];
+
+                    for (i, row) in luma.iter_mut().enumerate() {
+                        for (j, value) in row.iter_mut().enumerate() {
+                            let bgr = source.image.at_2d::<opencv::core::Vec3b>(
+                                y + tile_y + i as i32,
+                                x + tile_x + j as i32,
+                            )?;
+                            *value = calibration::luma(&[bgr[2], bgr[1], bgr[0]]);
+                        }
+                    }
+
+                    for (sum, coefficient) in coefficients.iter_mut().zip(dct::project(&luma)) {
+                        *sum += coefficient;
+                    }
+                }
             }
 
-            dbg!(final_frame);
-            u32_instructions.push(final_frame as u32);
-            u32_instructions.push(final_byte as u32);
+            binary_data.extend(coefficients.iter().map(|c| *c >= 0.0));
         }
     }
 
+    if current_frame == final_frame {
+        binary_data.truncate(final_bit as usize);
+    }
+
+    Ok(binary_data)
+}
+
+fn coded_bits(data: &Data) -> usize {
+    match data.out_mode {
+        OutputMode::Color | OutputMode::Qr => data.bytes.len() * 8,
+        _ => data.binary.len(),
+    }
+}
+
+fn frame_layout(total_bits: usize, frame_bits: usize) -> (usize, usize) {
+    let frames = total_bits.div_ceil(frame_bits);
+    let final_bits = total_bits - frames.saturating_sub(1) * frame_bits;
+
+    (frames, final_bits)
+}
+
+pub fn data_layout(data: &Data, settings: &Settings) -> (usize, usize) {
+    frame_layout(coded_bits(data), bits_per_frame(&data.out_mode, settings))
+}
+
+fn frame_fill(index: usize, final_frame: usize, final_bit: usize, frame_bits: usize) -> usize {
+    match index + 1 {
+        current if current < final_frame => frame_bits,
+        current if current == final_frame => final_bit,
+        _ => 0,
+    }
+}
+
+fn calibration_frames(out_mode: &OutputMode) -> u32 {
+    match out_mode {
+        OutputMode::Color | OutputMode::Quantized | OutputMode::YCbCr => 2,
+        OutputMode::Binary | OutputMode::Grayscale | OutputMode::Dct | OutputMode::Ldpc => 1,
+        OutputMode::Qr => 0,
+    }
+}
+
+pub fn bits_per_frame(out_mode: &OutputMode, settings: &Settings) -> usize {
+    let area = registration::embed_area(settings.width, settings.height, settings.region);
+    let columns = (area.width / settings.size) as usize;
+    let rows = (area.height / settings.size) as usize;
+    let bits = settings.bits as usize;
+
+    match out_mode {
+        OutputMode::Color => columns * rows * 24,
+        OutputMode::Binary | OutputMode::Ldpc => columns * rows,
+        OutputMode::Qr => qr::CHUNK_BYTES * 8,
+        OutputMode::Quantized => columns * rows * 3 * bits,
+        OutputMode::Grayscale => columns * rows * bits,
+        OutputMode::YCbCr => (columns / 2) * (rows / 2) * (4 * bits + 2),
+        OutputMode::Dct => columns * rows * dct::COEFFICIENTS.len(),
+    }
+}
+
+fn etch_bits(
+    source: &mut EmbedSource,
+    data: &Vec<bool>,
+    global_index: &mut usize,
+    out_mode: OutputMode,
+    bits: u8,
+) -> anyhow::Result<()> {
+    match out_mode {
+        OutputMode::Quantized => etch_quantized(source, data, global_index, bits),
+        OutputMode::Grayscale => etch_grayscale(source, data, global_index, bits),
+        OutputMode::YCbCr => etch_ycbcr(source, data, global_index, bits),
+        OutputMode::Dct => etch_dct(source, data, global_index),
+        _ => etch_bw(source, data, global_index),
+    }
+}
+
+pub fn instruction_words(settings: &Settings, data: &Data) -> Vec<u32> {
+    let mut u32_instructions: Vec<u32> = Vec::new();
+
+    u32_instructions.push(match data.out_mode {
+        OutputMode::Color => u32::MAX,
+        OutputMode::Binary => 0,
+        OutputMode::Quantized => 1,
+        OutputMode::Grayscale => 2,
+        OutputMode::YCbCr => 3,
+        OutputMode::Dct => 4,
+        OutputMode::Ldpc => 5,
+        OutputMode::Qr => 6,
+    });
+
+    let (final_frame, final_bit) = data_layout(data, settings);
+
+    u32_instructions.push(final_frame as u32);
+    u32_instructions.push(final_bit as u32);
     u32_instructions.push(settings.size as u32);
+    u32_instructions.push(calibration_frames(&data.out_mode));
+    u32_instructions.push(settings.bits as u32);
+    u32_instructions.push(settings.fec as u32);
+    u32_instructions.push(settings.width as u32);
+    u32_instructions.push(settings.height as u32);
+
+    u32_instructions.push(interleave_depth(&data.out_mode, settings, data.length) as u32);
+    u32_instructions.push(data.length as u32);
+    u32_instructions.push(settings.seed);
+
+    let region = registration::embed_area(settings.width, settings.height, settings.region);
+    u32_instructions.push(((region.x as u32) << 16) | region.y as u32);
+    u32_instructions.push(((region.width as u32) << 16) | region.height as u32);
     u32_instructions.push(u32::MAX);
 
-    let instruction_data = rip_binary_u32(u32_instructions)?;
+    u32_instructions
+}
 
-    let mut source = EmbedSource::new(instruction_size, settings.width, settings.height);
+fn etch_instructions(settings: &Settings, data: &Data) -> anyhow::Result<Mat> {
+    let instruction_size = 5;
+
+    let u32_instructions = instruction_words(settings, data);
+    let instruction_data = golay::encode(&rip_bits_u32(&u32_instructions), HEADER_COPIES);
+
+    let mut source = EmbedSource::new(
+        instruction_size,
+        registration::HEADER_WIDTH,
+        registration::HEADER_HEIGHT,
+        registration::header_area(),
+    );
     let mut index = 0;
 
-    match etch_bw(&mut source, &instruction_data, &mut index) {
-        AnyhowResult::Ok(_) => {}
-        AnyhowResult::Err(_) => {
-            println!("Instructions written successfully");
-        }
-    }
+    etch_bw(&mut source, &instruction_data, &mut index)?;
+    println!("Instructions written successfully");
+
+    registration::compose_header(&source.image, settings.width, settings.height)
+}
+
+pub const HEADER_WORDS: usize = 15;
 
-    Ok(source)
+const HEADER_COPIES: usize = 3;
+
+fn read_instruction_bits(source: &EmbedSource) -> anyhow::Result<Vec<bool>> {
+    let uncalibrated = Calibration::uncalibrated(source.frame_size.width, source.frame_size.height);
+    read_bw(source, &uncalibrated, 0, 1, 0)
 }
 
 fn read_instructions(
-    source: &EmbedSource,
+    binary_data: Vec<bool>,
     threads: usize,
-) -> anyhow::Result<(OutputMode, i32, i32, Settings)> {
-    let binary_data = read_bw(source, 0, 1, 0)?;
+) -> anyhow::Result<(OutputMode, i32, i32, u32, usize, Settings)> {
     let u32_data = translate_u32(binary_data)?;
 
+    if u32_data.len() < HEADER_WORDS || u32_data[14] != u32::MAX || u32_data[3] == 0 {
+        return Err(anyhow!("Instruction frame is corrupted"));
+    }
+
     let out_mode = match u32_data[0] {
         u32::MAX => OutputMode::Color,
-        _ => OutputMode::Binary,
+        1 => OutputMode::Quantized,
+        2 => OutputMode::Grayscale,
+        3 => OutputMode::YCbCr,
+        4 => OutputMode::Dct,
+        5 => OutputMode::Ldpc,
+        0 => OutputMode::Binary,
+        mode => return Err(anyhow!("Instruction frame names unknown mode {}", mode)),
     };
 
     let final_frame = u32_data[1] as i32;
     let final_bit = u32_data[2] as i32;
     let size = u32_data[3] as i32;
+    let calibration_frames = u32_data[4];
+
+    let width = u32_data[7] as i32;
+    let height = u32_data[8] as i32;
+
+    let mut settings = Settings::new(size, threads, 1337, width, height);
+    settings.bits = u32_data[5] as u8;
+    settings.fec = u32_data[6] as u8;
+    settings.interleave_depth = u32_data[9];
+    settings.seed = u32_data[11];
+    settings.region = [
+        (u32_data[12] >> 16) as i32,
+        (u32_data[12] & 0xFFFF) as i32,
+        (u32_data[13] >> 16) as i32,
+        (u32_data[13] & 0xFFFF) as i32,
+    ];
+
+    let length = u32_data[10] as usize;
+
+    Ok((
+        out_mode,
+        final_frame,
+        final_bit,
+        calibration_frames,
+        length,
+        settings,
+    ))
+}
 
-    let height = source.frame_size.height;
-    let width = source.frame_size.width;
+pub fn etch_frames(data: &Data, settings: &Settings) -> anyhow::Result<Vec<Mat>> {
+    if let OutputMode::Qr = data.out_mode {
+        return qr::etch_frames(&data.bytes, settings);
+    }
 
-    let settings = Settings::new(size, threads, 1337, width, height);
+    let size = settings.size;
+    let width = settings.width;
+    let region = registration::embed_area(width, settings.height, settings.region);
+    let height = settings.height;
+    let bits = settings.bits;
 
-    Ok((out_mode, final_frame, final_bit, settings))
-}
+    let mut spool = Vec::new();
 
-pub fn etch(path: &str, data: Data, settings: Settings) -> anyhow::Result<()> {
-    let _timer = Timer::new("Etching video");
+    let frame_bits = bits_per_frame(&data.out_mode, settings);
+    let (frame_count, _) = frame_layout(coded_bits(data), frame_bits);
+    if frame_count > registration::MAX_TAG_INDEX as usize + 1 {
+        return Err(anyhow!(
+            "{} data frames exceed the frame tag limit of {}, use a larger resolution or denser mode",
+            frame_count,
+            registration::MAX_TAG_INDEX + 1
+        ));
+    }
 
-    let mut spool = Vec::new();
+    let chunk_frames = frame_count.div_ceil(settings.threads.max(1)).max(1);
 
     match data.out_mode {
         OutputMode::Color => {
-            let length = data.bytes.len();
+            let frame_bytes = frame_bits / 8;
 
-            let frame_size = (settings.width * settings.height) as usize;
-            let frame_data_size = frame_size / settings.size.pow(2) as usize * 3;
-            let frame_length = length / frame_data_size;
-            let chunk_frame_size = (frame_length / settings.threads) + 1;
-            let chunk_data_size = chunk_frame_size * frame_data_size;
-
-            for chunk in data.bytes.chunks(chunk_data_size) {
+            for chunk in data.bytes.chunks(chunk_frames * frame_bytes) {
                 let chunk_copy = chunk.to_vec();
 
                 let thread = thread::spawn(move || {
                     let mut frames = Vec::new();
-                    let mut index: usize = 0;
-
-                    loop {
-                        let mut source =
-                            EmbedSource::new(settings.size, settings.width, settings.height);
-                        match etch_color(&mut source, &chunk_copy, &mut index) {
-                            AnyhowResult::Ok(_) => frames.push(source),
-                            AnyhowResult::Err(_) => {
-                                frames.push(source);
-                                println!("Embedding Thread Finished!");
-                                break;
-                            }
-                        }
+
+                    for frame_data in chunk_copy.chunks(frame_bytes) {
+                        let mut source = EmbedSource::new(size, width, height, region);
+                        etch_color(&mut source, &frame_data.to_vec(), &mut 0)?;
+                        frames.push(source);
                     }
-                    frames
+
+                    println!("Embedding Thread Finished!");
+                    anyhow::Ok(frames)
                 });
 
                 spool.push(thread);
             }
         }
-        OutputMode::Binary => {
-            let length = data.binary.len();
-
-            let frame_size = (settings.width * settings.height) as usize;
-            let frame_data_size = frame_size / settings.size.pow(2) as usize;
-            let frame_length = length / frame_data_size;
-            let chunk_frame_size = (frame_length / settings.threads) + 1;
-            let chunk_data_size = chunk_frame_size * frame_data_size;
-
-            for chunk in data.binary.chunks(chunk_data_size) {
+        OutputMode::Binary
+        | OutputMode::Quantized
+        | OutputMode::Grayscale
+        | OutputMode::YCbCr
+        | OutputMode::Dct
+        | OutputMode::Ldpc => {
+            let out_mode = data.out_mode;
+
+            for chunk in data.binary.chunks(chunk_frames * frame_bits) {
                 let chunk_copy = chunk.to_vec();
 
                 let thread = thread::spawn(move || {
                     let mut frames = Vec::new();
-                    let mut index: usize = 0;
-
-                    loop {
-                        let mut source =
-                            EmbedSource::new(settings.size, settings.width, settings.height);
-                        match etch_bw(&mut source, &chunk_copy, &mut index) {
-                            AnyhowResult::Ok(_) => frames.push(source),
-                            AnyhowResult::Err(_) => {
-                                frames.push(source);
-                                println!("Embedding Thread Finished!");
-                                break;
-                            }
-                        }
+
+                    for frame_data in chunk_copy.chunks(frame_bits) {
+                        let mut source = EmbedSource::new(size, width, height, region);
+                        etch_bits(&mut source, &frame_data.to_vec(), &mut 0, out_mode, bits)?;
+                        frames.push(source);
                     }
-                    frames
+
+                    println!("Embedding Thread Finished!");
+                    anyhow::Ok(frames)
                 });
 
                 spool.push(thread);
             }
         }
+        OutputMode::Qr => unreachable!(),
     }
 
     let mut complete_frames = Vec::new();
-    let instructional_frame = etch_instructions(&settings, &data)?;
-    complete_frames.push(instructional_frame);
+    let mut header_group = vec![etch_instructions(settings, data)?];
+
+    let background = overlay::render(settings)?;
+    let calibration_frame = calibration::etch_calibration(settings)?;
+    header_group.push(registration::compose(
+        &calibration_frame.image,
+        region,
+        background.as_ref(),
+        settings.width,
+        settings.height,
+        Tag::new(FrameKind::Calibration, 0),
+    )?);
+
+    if calibration_frames(&data.out_mode) > 1 {
+        let palette_frame = calibration::etch_palette(settings)?;
+        header_group.push(registration::compose(
+            &palette_frame.image,
+            region,
+            background.as_ref(),
+            settings.width,
+            settings.height,
+            Tag::new(FrameKind::Palette, 0),
+        )?);
+    }
+
+    for frame in &header_group {
+        complete_frames.push(frame.try_clone()?);
+    }
+
+    let mut index = 0;
 
     for thread in spool {
-        let frames = thread.join().unwrap();
-        complete_frames.extend(frames);
+        let frames = thread.join().unwrap()?;
+
+        for source in frames {
+            if settings.header_interval > 0 && index > 0 && index % settings.header_interval == 0 {
+                for frame in &header_group {
+                    complete_frames.push(frame.try_clone()?);
+                }
+            }
+
+            complete_frames.push(registration::compose(
+                &source.image,
+                region,
+                background.as_ref(),
+                settings.width,
+                settings.height,
+                Tag::new(FrameKind::Data, index),
+            )?);
+            index += 1;
+        }
     }
 
+    Ok(complete_frames)
+}
+
+pub fn render_frames(
+    data: &Data,
+    settings: &Settings,
+    title_card: Option<TitleCard>,
+) -> anyhow::Result<Vec<Mat>> {
+    let mut complete_frames = etch_frames(data, settings)?;
+
+    if let Some(card) = title_card {
+        complete_frames.insert(0, titlecard::render(&card, data, settings)?);
+    }
+
+    Ok(complete_frames)
+}
+
+pub fn etch(
+    path: &str,
+    data: Data,
+    settings: Settings,
+    title_card: Option<TitleCard>,
+) -> anyhow::Result<()> {
+    let _timer = Timer::new("Etching video");
+
+    let complete_frames = render_frames(&data, &settings, title_card)?;
+
     let fourcc = VideoWriter::fourcc('p', 'n', 'g', ' ')?;
 
-    let frame_size = complete_frames[1].frame_size;
+    let frame_size = Size::new(settings.width, settings.height);
     let video = VideoWriter::new(path, fourcc, settings.fps, frame_size, true);
 
     let mut video = match video {
@@ -459,52 +1056,403 @@ pub fn etch(path: &str, data: Data, settings: Settings) -> anyhow::Result<()> {
     };
 
     for frame in complete_frames {
-        let image = frame.image;
-        video.write(&image).unwrap();
+        video.write(&frame).unwrap();
     }
     video.release().unwrap();
     println!("Video Etched Successfully at {}", path);
     Ok(())
 }
 
-pub fn read(path: &str, threads: usize) -> anyhow::Result<Vec<u8>> {
+pub fn read(path: &str, threads: usize, capture: bool) -> anyhow::Result<Vec<u8>> {
+    let open = || -> anyhow::Result<_> {
+        let video = VideoCapture::from_file(path, CAP_ANY)?;
+        Ok(video_frames(video))
+    };
+
+    let subtitle = subtitles::read(path).header;
+    let audio = audio::read_headers(path);
+
+    read_frames_with_headers(open, threads, capture, &SideHeaders { audio, subtitle })
+}
+
+pub fn video_duration(path: &str, fps: f64) -> anyhow::Result<f64> {
+    let video = VideoCapture::from_file(path, CAP_ANY)?;
+    Ok(video.get(CAP_PROP_FRAME_COUNT)? / fps.max(1.0))
+}
+
+pub fn video_frames(mut video: VideoCapture) -> impl Iterator<Item = Mat> {
+    std::iter::from_fn(move || {
+        let mut frame = Mat::default();
+
+        match video.read(&mut frame) {
+            Ok(true) if frame.cols() > 0 => Some(frame),
+            _ => None,
+        }
+    })
+}
+
+fn add_votes(votes: &mut Vec<i32>, bits: Vec<bool>) {
+    votes.resize(votes.len().max(bits.len()), 0);
+    for (vote, bit) in votes.iter_mut().zip(bits) {
+        *vote += if bit { 1 } else { -1 };
+    }
+}
+
+#[derive(Default)]
+pub struct SideHeaders {
+    pub audio: Vec<Vec<u32>>,
+
+    pub subtitle: Option<Vec<u32>>,
+}
+
+pub fn read_frames<I: Iterator<Item = Mat>>(
+    open: impl Fn() -> anyhow::Result<I>,
+    threads: usize,
+    capture: bool,
+) -> anyhow::Result<Vec<u8>> {
+    read_frames_with_headers(open, threads, capture, &SideHeaders::default())
+}
+
+pub fn read_frames_with_headers<I: Iterator<Item = Mat>>(
+    open: impl Fn() -> anyhow::Result<I>,
+    threads: usize,
+    capture: bool,
+    side_headers: &SideHeaders,
+) -> anyhow::Result<Vec<u8>> {
     let _timer = Timer::new("Dislodging video");
     const INSTRUCTION_SIZE: i32 = 5;
 
-    let mut video = VideoCapture::from_file(path, CAP_ANY).expect("Could not open video path");
-    let mut frame = Mat::default();
+    let mut votes: Vec<i32> = Vec::new();
+    let mut header_copies = 0;
+    let mut calibration_frame = None;
+    let mut palette_frame = None;
 
-    video.read(&mut frame).unwrap();
-    let instruction_source = EmbedSource::from(frame.clone(), INSTRUCTION_SIZE, true)
-        .expect("Could not create instruction source");
-    let (out_mode, final_frame, final_byte, settings) =
-        read_instructions(&instruction_source, threads)?;
+    for frame in open()? {
+        let Some(tag) = registration::read_tag(&frame, capture)? else {
+            continue;
+        };
 
-    let mut byte_data = Vec::new();
-    let mut current_frame = 1;
+        match tag.kind {
+            FrameKind::Header => {
+                let source = EmbedSource::from(
+                    registration::register_header(&frame, capture)?,
+                    INSTRUCTION_SIZE,
+                );
+                add_votes(&mut votes, read_instruction_bits(&source)?);
+                header_copies += 1;
+            }
+            FrameKind::Calibration if calibration_frame.is_none() => {
+                calibration_frame = Some(frame)
+            }
+            FrameKind::Palette if palette_frame.is_none() => palette_frame = Some(frame),
+            _ => {}
+        }
+    }
 
-    while video.read(&mut frame).unwrap() && frame.cols() > 0 {
-        if current_frame % 20 == 0 {
-            println!("Reading frame {}", current_frame);
+    for words in &side_headers.audio {
+        add_votes(
+            &mut votes,
+            golay::encode(&rip_bits_u32(words), HEADER_COPIES),
+        );
+        header_copies += 1;
+    }
+
+    let subtitle_bits = side_headers.subtitle.as_deref().map(rip_bits_u32);
+
+    let header_bits = match (header_copies, subtitle_bits) {
+        (0, None) => {
+            return match qr::read(open()?)? {
+                Some(bytes) => Ok(bytes),
+                None => Err(anyhow!("Video does not contain an instruction frame")),
+            };
         }
+        (0, Some(subtitle_bits)) => {
+            println!("Instructions read from the subtitle track");
+            subtitle_bits
+        }
+        (_, subtitle_bits) => {
+            let code: Vec<bool> = votes.iter().map(|vote| *vote > 0).collect();
+            let (header_bits, corrected) = golay::decode(&code, HEADER_WORDS * 32, HEADER_COPIES);
+
+            println!(
+                "Instructions voted from {} copies, {} header bits corrected",
+                header_copies, corrected
+            );
+
+            // The subtitle header is a single unprotected copy, it only wins when the vote is unusable
+            match subtitle_bits {
+                Some(subtitle_bits) if subtitle_bits == header_bits => {
+                    println!("Subtitle track header agrees with the voted instructions");
+                    header_bits
+                }
+                Some(subtitle_bits)
+                    if read_instructions(header_bits.clone(), threads).is_err()
+                        && read_instructions(subtitle_bits.clone(), threads).is_ok() =>
+                {
+                    println!("Voted instructions are invalid, using the subtitle track header");
+                    subtitle_bits
+                }
+                Some(_) => {
+                    println!(
+                        "Warning: subtitle track header disagrees with the voted instructions and was ignored"
+                    );
+                    header_bits
+                }
+                None => header_bits,
+            }
+        }
+    };
+
+    let (out_mode, final_frame, final_byte, calibration_frames, length, settings) =
+        read_instructions(header_bits, threads)?;
+
+    let region = registration::embed_area(settings.width, settings.height, settings.region);
 
-        let source =
-            EmbedSource::from(frame.clone(), settings.size, false).expect("Reading frame failed");
+    let register = |frame: Mat| -> anyhow::Result<EmbedSource> {
+        let data =
+            registration::register(&frame, settings.width, settings.height, region, capture)?;
+        Ok(EmbedSource::from(data, settings.size))
+    };
+
+    let mut calibration = Calibration::uncalibrated(settings.width, settings.height);
+
+    if calibration_frames > 0 {
+        let frame = calibration_frame.ok_or_else(|| anyhow!("Calibration frame is missing"))?;
+        calibration = calibration::read_calibration(&register(frame)?)?;
+        calibration.report();
+    }
+
+    if calibration_frames > 1 {
+        let frame = palette_frame.ok_or_else(|| anyhow!("Palette frame is missing"))?;
+        calibration.color = calibration::read_palette(&register(frame)?)?;
+    }
 
-        let frame_data = match out_mode {
-            OutputMode::Color => read_color(&source, current_frame, i32::MAX, final_byte)
-                .expect("Failed to read color frame"),
-            OutputMode::Binary => {
-                let binary_data = read_bw(&source, current_frame, final_frame, final_byte)
-                    .expect("Failed to read binary frame");
-                translate_u8(binary_data).expect("Failed to translate binary data")
+    let frame_bits = bits_per_frame(&out_mode, &settings);
+    let final_frame_index = final_frame as usize;
+    let fill = |index: usize| frame_fill(index, final_frame_index, final_byte as usize, frame_bits);
+
+    let mut slots: Vec<Option<Vec<bool>>> = vec![None; final_frame_index];
+    let mut soft_slots: Vec<Option<Vec<f64>>> = vec![None; final_frame_index];
+
+    for frame in open()? {
+        let Some(tag) = registration::read_tag(&frame, capture)? else {
+            continue;
+        };
+
+        let index = tag.index as usize;
+
+        if tag.kind != FrameKind::Data
+            || index >= final_frame_index
+            || slots[index].is_some()
+            || soft_slots[index].is_some()
+        {
+            continue;
+        }
+
+        if index % 20 == 0 {
+            println!("Reading frame {}", index);
+        }
+
+        let source = register(frame)?;
+        let current_frame = index as i32 + 1;
+
+        if let OutputMode::Ldpc = out_mode {
+            let mut soft = read_soft(
+                &source,
+                &calibration,
+                current_frame,
+                final_frame,
+                final_byte,
+            )?;
+            soft.resize(fill(index), 0.0);
+            soft_slots[index] = Some(soft);
+            continue;
+        }
+
+        let mut frame_data = match out_mode {
+            OutputMode::Color => {
+                let byte_data = read_color(
+                    &source,
+                    &calibration,
+                    current_frame,
+                    final_frame,
+                    final_byte / 8,
+                )?;
+                rip_bits(&byte_data)
             }
+            OutputMode::Binary => read_bw(
+                &source,
+                &calibration,
+                current_frame,
+                final_frame,
+                final_byte,
+            )?,
+            OutputMode::Quantized => read_quantized(
+                &source,
+                &calibration,
+                current_frame,
+                final_frame,
+                final_byte,
+                settings.bits,
+            )?,
+            OutputMode::Grayscale => read_grayscale(
+                &source,
+                &calibration,
+                current_frame,
+                final_frame,
+                final_byte,
+                settings.bits,
+            )?,
+            OutputMode::YCbCr => read_ycbcr(
+                &source,
+                &calibration,
+                current_frame,
+                final_frame,
+                final_byte,
+                settings.bits,
+            )?,
+            OutputMode::Dct => read_dct(&source, current_frame, final_frame, final_byte)?,
+            OutputMode::Ldpc | OutputMode::Qr => unreachable!(),
         };
 
-        byte_data.extend(frame_data);
-        current_frame += 1;
+        frame_data.resize(fill(index), false);
+        slots[index] = Some(frame_data);
     }
 
+    let missing = match out_mode {
+        OutputMode::Ldpc => soft_slots.iter().filter(|slot| slot.is_none()).count(),
+        _ => slots.iter().filter(|slot| slot.is_none()).count(),
+    };
+
+    if missing > 0 {
+        println!(
+            "Warning: {} data frames are missing and were zero filled",
+            missing
+        );
+    }
+
+    let byte_data = match out_mode {
+        OutputMode::Ldpc => {
+            let mut soft = Vec::new();
+            for (index, slot) in soft_slots.into_iter().enumerate() {
+                soft.extend(slot.unwrap_or_else(|| vec![0.0; fill(index)]));
+            }
+
+            let (binary_data, failed) = ldpc::decode(&soft, length * 8);
+            if failed > 0 {
+                println!("Warning: {} LDPC blocks did not converge", failed);
+            }
+            translate_u8(binary_data)?
+        }
+        _ => {
+            let mut binary_data = Vec::new();
+            for (index, slot) in slots.into_iter().enumerate() {
+                binary_data.extend(slot.unwrap_or_else(|| vec![false; fill(index)]));
+            }
+
+            translate_u8(binary_data)?
+        }
+    };
+
     println!("Video read successfully");
-    Ok(byte_data)
+    let byte_data = whiten::scramble(&byte_data, settings.seed as u64);
+    let byte_data =
+        interleave::deinterleave(&byte_data, settings.interleave_depth as usize, length);
+    Ok(fec::decode(&byte_data, settings.fec))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [OutputMode; 8] = [
+        OutputMode::Color,
+        OutputMode::Binary,
+        OutputMode::Quantized,
+        OutputMode::Grayscale,
+        OutputMode::YCbCr,
+        OutputMode::Dct,
+        OutputMode::Ldpc,
+        OutputMode::Qr,
+    ];
+
+    fn settings(threads: usize) -> Settings {
+        let mut settings = Settings::new(8, threads, 30, 640, 360);
+        settings.bits = 2;
+        settings.fec = 3;
+        settings.seed = 99;
+        settings
+    }
+
+    fn payload(length: usize) -> Vec<u8> {
+        let mut rng = crate::prng::Rng::new(5);
+        (0..length).map(|_| rng.next_u64() as u8).collect()
+    }
+
+    #[test]
+    fn frame_layout_splits_bits_into_frames() {
+        assert_eq!(frame_layout(0, 100), (0, 0));
+        assert_eq!(frame_layout(40, 100), (1, 40));
+        assert_eq!(frame_layout(300, 100), (3, 100));
+        assert_eq!(frame_layout(301, 100), (4, 1));
+    }
+
+    #[test]
+    fn frame_fill_covers_every_bit_once() {
+        let (final_frame, final_bit) = frame_layout(301, 100);
+        let fills: Vec<usize> = (0..6)
+            .map(|index| frame_fill(index, final_frame, final_bit, 100))
+            .collect();
+
+        assert_eq!(fills, [100, 100, 100, 1, 0, 0]);
+    }
+
+    #[test]
+    fn data_layout_ends_on_a_partial_frame_in_every_mode() {
+        let settings = settings(1);
+
+        for mode in MODES {
+            let data = prepare_data(payload(5003), mode, &settings).unwrap();
+            let frame_bits = bits_per_frame(&mode, &settings);
+            let (final_frame, final_bit) = data_layout(&data, &settings);
+
+            assert!(final_frame > 1, "{:?}", mode);
+            assert!(final_bit > 0 && final_bit < frame_bits, "{:?}", mode);
+
+            let total: usize = (0..final_frame + 1)
+                .map(|index| frame_fill(index, final_frame, final_bit, frame_bits))
+                .sum();
+            assert_eq!(total, coded_bits(&data), "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn threaded_read_matches_single_thread() {
+        let payload = payload(5003);
+
+        for mode in [OutputMode::Binary, OutputMode::Grayscale, OutputMode::Color] {
+            let data = prepare_data(payload.clone(), mode, &settings(4)).unwrap();
+            let frames = etch_frames(&data, &settings(4)).unwrap();
+            let open = || {
+                let copies: Vec<Mat> = frames.iter().map(|f| f.try_clone().unwrap()).collect();
+                Ok(copies.into_iter())
+            };
+
+            let single = read_frames(open, 1, false).unwrap();
+            let threaded = read_frames(open, 4, false).unwrap();
+
+            assert_eq!(single, payload, "{:?}", mode);
+            assert_eq!(threaded, payload, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn header_words_round_trip_through_bits() {
+        let words = [0, 1, u32::MAX, 0xDEAD_BEEF];
+        let bits = rip_bits_u32(&words);
+
+        assert_eq!(bits.len(), 128);
+        assert!(bits[63]);
+        assert_eq!(translate_u32(bits).unwrap(), words);
+    }
 }
@@ -0,0 +1,32 @@
+pub fn encode(data: &[u8], copies: u8) -> Vec<u8> {
+    let copies = copies.max(1) as usize;
+    let mut encoded = Vec::with_capacity(data.len() * copies);
+
+    for byte in data {
+        for _ in 0..copies {
+            encoded.push(*byte);
+        }
+    }
+
+    encoded
+}
+
+pub fn decode(data: &[u8], copies: u8) -> Vec<u8> {
+    let copies = copies.max(1) as usize;
+
+    data.chunks(copies)
+        .map(|chunk| {
+            let mut byte = 0u8;
+
+            for bit in 0..8 {
+                let ones = chunk.iter().filter(|b| (*b >> bit) & 1 == 1).count();
+
+                if ones * 2 > chunk.len() {
+                    byte |= 1 << bit;
+                }
+            }
+
+            byte
+        })
+        .collect()
+}
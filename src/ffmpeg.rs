@@ -0,0 +1,72 @@
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::process::{self, Command};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+
+pub fn run(args: &[&str]) -> bool {
+    Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "error"])
+        .args(args)
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+pub fn has_stream(path: &str, codec_type: &str) -> bool {
+    Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-show_entries",
+            "stream=codec_type",
+            "-of",
+            "csv=p=0",
+            path,
+        ])
+        .output()
+        .is_ok_and(|output| {
+            output.status.success()
+                && String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .any(|line| line.trim() == codec_type)
+        })
+}
+
+pub struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    pub fn new(extension: &str) -> anyhow::Result<Self> {
+        for attempt in 0..16 {
+            let nanos = SystemTime::now().duration_since(UNIX_EPOCH)?.subsec_nanos();
+            let path = std::env::temp_dir().join(format!(
+                "vidvault-{}-{}-{}.{}",
+                process::id(),
+                nanos,
+                attempt,
+                extension
+            ));
+
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(TempFile { path }),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Err(anyhow!("Could not create a temporary file"))
+    }
+
+    pub fn path(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
@@ -0,0 +1,504 @@
+use std::collections::HashMap;
+use std::fs;
+
+use anyhow::anyhow;
+
+use opencv::core::{CV_8UC3, Mat, Scalar};
+use opencv::prelude::*;
+
+use crate::calibration;
+use crate::quantize;
+
+const MAX_CODE: u16 = 4096;
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u32) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += width;
+
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+fn lzw_encode(indices: &[u8], min_size: u32) -> Vec<u8> {
+    let clear = 1u16 << min_size;
+    let end = clear + 1;
+
+    let mut writer = BitWriter {
+        bytes: Vec::new(),
+        buffer: 0,
+        bits: 0,
+    };
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut width = min_size + 1;
+
+    writer.write(clear, width);
+
+    let Some((&first, rest)) = indices.split_first() else {
+        writer.write(end, width);
+        return writer.finish();
+    };
+
+    let mut prefix = first as u16;
+
+    for &index in rest {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+
+        writer.write(prefix, width);
+
+        if next < MAX_CODE {
+            table.insert((prefix, index), next);
+            next += 1;
+
+            if next > (1 << width) && width < 12 {
+                width += 1;
+            }
+        } else {
+            writer.write(clear, width);
+            table.clear();
+            next = end + 1;
+            width = min_size + 1;
+        }
+
+        prefix = index as u16;
+    }
+
+    writer.write(prefix, width);
+    writer.write(end, width);
+    writer.finish()
+}
+
+fn lzw_decode(data: &[u8], min_size: u32, pixels: usize) -> anyhow::Result<Vec<u8>> {
+    let clear = 1u16 << min_size;
+    let end = clear + 1;
+
+    let mut table: Vec<(Option<u16>, u8)> = (0..clear).map(|i| (None, i as u8)).collect();
+    table.push((None, 0));
+    table.push((None, 0));
+
+    let mut output = Vec::with_capacity(pixels);
+    let mut width = min_size + 1;
+    let mut previous: Option<u16> = None;
+
+    let mut buffer = 0u32;
+    let mut bits = 0u32;
+    let mut bytes = data.iter();
+
+    let expand = |table: &Vec<(Option<u16>, u8)>, mut code: u16| {
+        let mut sequence = Vec::new();
+        loop {
+            let (prefix, value) = table[code as usize];
+            sequence.push(value);
+            match prefix {
+                Some(prefix) => code = prefix,
+                None => break,
+            }
+        }
+        sequence.reverse();
+        sequence
+    };
+
+    loop {
+        while bits < width {
+            let Some(&byte) = bytes.next() else {
+                return Ok(output);
+            };
+            buffer |= (byte as u32) << bits;
+            bits += 8;
+        }
+
+        let code = (buffer & ((1 << width) - 1)) as u16;
+        buffer >>= width;
+        bits -= width;
+
+        if code == clear {
+            table.truncate(end as usize + 1);
+            width = min_size + 1;
+            previous = None;
+            continue;
+        }
+
+        if code == end {
+            return Ok(output);
+        }
+
+        if code as usize > table.len() || (previous.is_none() && code as usize == table.len()) {
+            return Err(anyhow!("Invalid LZW code in GIF"));
+        }
+
+        let sequence = match previous {
+            None => expand(&table, code),
+            Some(previous) => {
+                let sequence = if (code as usize) < table.len() {
+                    expand(&table, code)
+                } else {
+                    let mut sequence = expand(&table, previous);
+                    sequence.push(sequence[0]);
+                    sequence
+                };
+
+                if table.len() < MAX_CODE as usize {
+                    table.push((Some(previous), sequence[0]));
+                }
+
+                sequence
+            }
+        };
+
+        output.extend(&sequence);
+        previous = Some(code);
+
+        if table.len() == (1 << width) && width < 12 {
+            width += 1;
+        }
+    }
+}
+
+fn sub_blocks(data: &[u8]) -> Vec<u8> {
+    let mut blocks = Vec::new();
+
+    for chunk in data.chunks(255) {
+        blocks.push(chunk.len() as u8);
+        blocks.extend(chunk);
+    }
+
+    blocks.push(0);
+    blocks
+}
+
+pub fn palette(bits: u8) -> Vec<u8> {
+    quantize::levels(bits)
+}
+
+pub fn encode(width: u16, height: u16, levels: &[u8], frames: &[Vec<u8>], delay: u16) -> Vec<u8> {
+    let table_bits = (levels.len().max(2) as u32)
+        .next_power_of_two()
+        .trailing_zeros();
+    let min_size = table_bits.max(2);
+
+    let mut gif = b"GIF89a".to_vec();
+    gif.extend(width.to_le_bytes());
+    gif.extend(height.to_le_bytes());
+    gif.push(0x80 | 0x70 | (table_bits as u8 - 1));
+    gif.extend([0, 0]);
+
+    for i in 0..(1usize << table_bits) {
+        let level = levels.get(i).copied().unwrap_or(0);
+        gif.extend([level, level, level]);
+    }
+
+    gif.extend([0x21, 0xFF, 0x0B]);
+    gif.extend(b"NETSCAPE2.0");
+    gif.extend([0x03, 0x01, 0x00, 0x00, 0x00]);
+
+    for indices in frames {
+        gif.extend([0x21, 0xF9, 0x04, 0x04]);
+        gif.extend(delay.to_le_bytes());
+        gif.extend([0x00, 0x00]);
+
+        gif.push(0x2C);
+        gif.extend([0, 0, 0, 0]);
+        gif.extend(width.to_le_bytes());
+        gif.extend(height.to_le_bytes());
+        gif.push(0);
+
+        gif.push(min_size as u8);
+        gif.extend(sub_blocks(&lzw_encode(indices, min_size)));
+    }
+
+    gif.push(0x3B);
+    gif
+}
+
+fn read_sub_blocks(data: &[u8], position: &mut usize) -> anyhow::Result<Vec<u8>> {
+    let mut blocks = Vec::new();
+
+    loop {
+        let size = *data
+            .get(*position)
+            .ok_or_else(|| anyhow!("GIF ended inside a data block"))? as usize;
+        *position += 1;
+
+        if size == 0 {
+            return Ok(blocks);
+        }
+
+        blocks.extend(
+            data.get(*position..*position + size)
+                .ok_or_else(|| anyhow!("GIF ended inside a data block"))?,
+        );
+        *position += size;
+    }
+}
+
+fn deinterlace(rows: Vec<&[u8]>) -> Vec<&[u8]> {
+    let height = rows.len();
+    let mut order = Vec::with_capacity(height);
+
+    for (start, step) in [(0, 8), (4, 8), (2, 4), (1, 2)] {
+        order.extend((start..height).step_by(step));
+    }
+
+    let mut sorted = vec![rows[0]; height];
+    for (row, target) in rows.into_iter().zip(order) {
+        sorted[target] = row;
+    }
+    sorted
+}
+
+pub fn decode(data: &[u8]) -> anyhow::Result<(usize, usize, Vec<Vec<u8>>)> {
+    if data.len() < 13 || !data.starts_with(b"GIF8") {
+        return Err(anyhow!("Not a GIF file"));
+    }
+
+    let width = u16::from_le_bytes([data[6], data[7]]) as usize;
+    let height = u16::from_le_bytes([data[8], data[9]]) as usize;
+    let flags = data[10];
+    let mut position = 13;
+
+    let mut global_table = Vec::new();
+    if flags & 0x80 != 0 {
+        let size = 3 << ((flags & 0x07) + 1);
+        global_table = data
+            .get(position..position + size)
+            .ok_or_else(|| anyhow!("GIF colour table is truncated"))?
+            .to_vec();
+        position += size;
+    }
+
+    let mut canvas = vec![0u8; width * height * 3];
+    let mut frames = Vec::new();
+
+    while let Some(&block) = data.get(position) {
+        position += 1;
+
+        match block {
+            0x21 => {
+                position += 1;
+                read_sub_blocks(data, &mut position)?;
+            }
+            0x2C => {
+                let descriptor = data
+                    .get(position..position + 9)
+                    .ok_or_else(|| anyhow!("GIF image descriptor is truncated"))?;
+                let left = u16::from_le_bytes([descriptor[0], descriptor[1]]) as usize;
+                let top = u16::from_le_bytes([descriptor[2], descriptor[3]]) as usize;
+                let frame_width = u16::from_le_bytes([descriptor[4], descriptor[5]]) as usize;
+                let frame_height = u16::from_le_bytes([descriptor[6], descriptor[7]]) as usize;
+                let frame_flags = descriptor[8];
+                position += 9;
+
+                let mut table = &global_table;
+                let local_table;
+                if frame_flags & 0x80 != 0 {
+                    let size = 3 << ((frame_flags & 0x07) + 1);
+                    local_table = data
+                        .get(position..position + size)
+                        .ok_or_else(|| anyhow!("GIF colour table is truncated"))?
+                        .to_vec();
+                    table = &local_table;
+                    position += size;
+                }
+
+                let min_size = *data
+                    .get(position)
+                    .ok_or_else(|| anyhow!("GIF image data is truncated"))?
+                    as u32;
+                position += 1;
+
+                let compressed = read_sub_blocks(data, &mut position)?;
+                let mut indices = lzw_decode(&compressed, min_size, frame_width * frame_height)?;
+                indices.resize(frame_width * frame_height, 0);
+
+                let mut rows: Vec<&[u8]> = indices.chunks(frame_width.max(1)).collect();
+                if frame_flags & 0x40 != 0 && !rows.is_empty() {
+                    rows = deinterlace(rows);
+                }
+
+                for (y, row) in rows.iter().enumerate() {
+                    for (x, &index) in row.iter().enumerate() {
+                        let (canvas_x, canvas_y) = (left + x, top + y);
+                        if canvas_x >= width || canvas_y >= height {
+                            continue;
+                        }
+
+                        let color = table.get(index as usize * 3..index as usize * 3 + 3);
+                        if let Some(color) = color {
+                            let offset = (canvas_y * width + canvas_x) * 3;
+                            canvas[offset..offset + 3].copy_from_slice(color);
+                        }
+                    }
+                }
+
+                frames.push(canvas.clone());
+            }
+            0x3B => break,
+            _ => return Err(anyhow!("Unknown GIF block 0x{:02X}", block)),
+        }
+    }
+
+    Ok((width, height, frames))
+}
+
+pub fn write(path: &str, frames: &[Mat], bits: u8, fps: f64) -> anyhow::Result<()> {
+    let levels = palette(bits);
+    let first = frames
+        .first()
+        .ok_or_else(|| anyhow!("No frames to write"))?;
+    let width = first.cols();
+    let height = first.rows();
+
+    let mut indexed = Vec::new();
+
+    for frame in frames {
+        let frame = frame.try_clone()?;
+        let indices = frame
+            .data_bytes()?
+            .chunks(3)
+            .map(|bgr| {
+                let luma = calibration::luma(&[bgr[2], bgr[1], bgr[0]]);
+                levels
+                    .iter()
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| {
+                        (luma - **a as f64)
+                            .abs()
+                            .total_cmp(&(luma - **b as f64).abs())
+                    })
+                    .map(|(i, _)| i as u8)
+                    .unwrap_or(0)
+            })
+            .collect();
+        indexed.push(indices);
+    }
+
+    let delay = (100.0 / fps.max(1.0)).round() as u16;
+    let gif = encode(width as u16, height as u16, &levels, &indexed, delay);
+
+    fs::write(path, gif)?;
+    println!("{} frames written to {}", frames.len(), path);
+    Ok(())
+}
+
+pub fn read(path: &str) -> anyhow::Result<Vec<Mat>> {
+    let (width, height, frames) = decode(&fs::read(path)?)?;
+    let mut mats = Vec::new();
+
+    for rgb in frames {
+        let mut mat = Mat::new_rows_cols_with_default(
+            height as i32,
+            width as i32,
+            CV_8UC3,
+            Scalar::all(0.0),
+        )?;
+
+        for (bgr, rgb) in mat.data_bytes_mut()?.chunks_mut(3).zip(rgb.chunks(3)) {
+            bgr.copy_from_slice(&[rgb[2], rgb[1], rgb[0]]);
+        }
+
+        mats.push(mat);
+    }
+
+    Ok(mats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prng::Rng;
+
+    fn random_indices(count: usize, symbols: u64, seed: u64) -> Vec<u8> {
+        let mut rng = Rng::new(seed);
+        (0..count)
+            .map(|_| (rng.next_u64() % symbols) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn lzw_round_trips_small_inputs() {
+        for min_size in [2, 3, 8] {
+            let symbols = 1u64 << min_size;
+
+            for indices in [
+                Vec::new(),
+                vec![0],
+                vec![1; 1000],
+                random_indices(300, symbols, min_size as u64),
+            ] {
+                let encoded = lzw_encode(&indices, min_size);
+                assert_eq!(
+                    lzw_decode(&encoded, min_size, indices.len()).unwrap(),
+                    indices
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lzw_round_trips_past_the_code_table_limit() {
+        // 640x360 of random 2 bit pixels fills the 4096 entry table many times over
+        let indices = random_indices(640 * 360, 4, 9);
+        let encoded = lzw_encode(&indices, 2);
+
+        assert!(encoded.len() * 8 / 12 > 4 * MAX_CODE as usize);
+        assert_eq!(lzw_decode(&encoded, 2, indices.len()).unwrap(), indices);
+
+        let indices = random_indices(640 * 360, 256, 10);
+        assert_eq!(
+            lzw_decode(&lzw_encode(&indices, 8), 8, indices.len()).unwrap(),
+            indices
+        );
+    }
+
+    #[test]
+    fn file_round_trips_frames() {
+        let levels = palette(2);
+        let (width, height) = (96, 54);
+        let frames: Vec<Vec<u8>> = (0..3)
+            .map(|seed| random_indices(width * height, levels.len() as u64, seed))
+            .collect();
+
+        let gif = encode(width as u16, height as u16, &levels, &frames, 4);
+        let (decoded_width, decoded_height, decoded) = decode(&gif).unwrap();
+
+        assert_eq!((decoded_width, decoded_height), (width, height));
+        assert_eq!(decoded.len(), frames.len());
+
+        for (rgb, indices) in decoded.iter().zip(&frames) {
+            let expected: Vec<u8> = indices
+                .iter()
+                .flat_map(|index| [levels[*index as usize]; 3])
+                .collect();
+            assert_eq!(rgb, &expected);
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let gif = encode(8, 8, &palette(1), &[vec![1; 64]], 4);
+
+        assert!(decode(b"PNG").is_err());
+        assert!(decode(&gif[..gif.len() - 4]).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use std::sync::OnceLock;
+
+const GENERATOR: u32 = 0xC75;
+
+const DATA_BITS: usize = 12;
+
+const CODE_BITS: usize = 23;
+
+fn remainder(mut value: u32) -> u32 {
+    for bit in (11..CODE_BITS).rev() {
+        if (value >> bit) & 1 == 1 {
+            value ^= GENERATOR << (bit - 11);
+        }
+    }
+
+    value
+}
+
+fn syndromes() -> &'static Vec<u32> {
+    static TABLE: OnceLock<Vec<u32>> = OnceLock::new();
+
+    TABLE.get_or_init(|| {
+        let mut table = vec![0u32; 1 << 11];
+
+        for a in 0..CODE_BITS {
+            for b in a..CODE_BITS {
+                for c in b..CODE_BITS {
+                    let pattern = (1 << a) | (1 << b) | (1 << c);
+                    let syndrome = remainder(pattern) as usize;
+
+                    if table[syndrome] == 0 || pattern.count_ones() < table[syndrome].count_ones() {
+                        table[syndrome] = pattern;
+                    }
+                }
+            }
+        }
+
+        table[0] = 0;
+        table
+    })
+}
+
+fn encode_word(data: u16) -> u32 {
+    let shifted = (data as u32 & 0xFFF) << 11;
+    shifted | remainder(shifted)
+}
+
+fn decode_word(word: u32) -> (u16, u32) {
+    let word = word & 0x7F_FFFF;
+    let error = syndromes()[remainder(word) as usize];
+
+    (((word ^ error) >> 11) as u16, error.count_ones())
+}
+
+fn to_value(bits: &[bool]) -> u32 {
+    bits.iter().fold(0u32, |v, b| (v << 1) | *b as u32)
+}
+
+fn push_value(bits: &mut Vec<bool>, value: u32, width: usize) {
+    bits.extend((0..width).rev().map(|i| (value >> i) & 1 == 1));
+}
+
+pub fn encode(data: &[bool], copies: usize) -> Vec<bool> {
+    let mut code = Vec::new();
+
+    for chunk in data.chunks(DATA_BITS) {
+        let mut word = to_value(chunk);
+        word <<= DATA_BITS - chunk.len();
+        push_value(&mut code, encode_word(word as u16), CODE_BITS);
+    }
+
+    code.repeat(copies.max(1))
+}
+
+pub fn decode(code: &[bool], data_len: usize, copies: usize) -> (Vec<bool>, usize) {
+    let copies = copies.max(1);
+    let length = data_len.div_ceil(DATA_BITS) * CODE_BITS;
+    let mut corrected = 0;
+
+    let voted: Vec<bool> = (0..length)
+        .map(|i| {
+            let ones = (0..copies)
+                .filter(|copy| code.get(copy * length + i).copied().unwrap_or(false))
+                .count();
+            let bit = ones * 2 > copies;
+
+            corrected += if bit { copies - ones } else { ones };
+            bit
+        })
+        .collect();
+
+    let mut data = Vec::with_capacity(data_len);
+
+    for word in voted.chunks(CODE_BITS) {
+        let (value, errors) = decode_word(to_value(word));
+        corrected += errors as usize;
+        push_value(&mut data, value as u32, DATA_BITS);
+    }
+
+    data.truncate(data_len);
+    (data, corrected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prng::Rng;
+
+    #[test]
+    fn corrects_three_errors_per_word() {
+        let mut rng = Rng::new(1);
+        let data: Vec<bool> = (0..DATA_BITS * 40)
+            .map(|_| rng.next_u64() & 1 == 1)
+            .collect();
+        let mut code = encode(&data, 1);
+
+        for word in code.chunks_mut(CODE_BITS) {
+            let mut flipped = Vec::new();
+
+            while flipped.len() < 3 {
+                let bit = (rng.next_u64() % CODE_BITS as u64) as usize;
+                if !flipped.contains(&bit) {
+                    word[bit] = !word[bit];
+                    flipped.push(bit);
+                }
+            }
+        }
+
+        let (decoded, corrected) = decode(&code, data.len(), 1);
+        assert_eq!(decoded, data);
+        assert_eq!(corrected, 3 * 40);
+    }
+
+    #[test]
+    fn copies_outvote_a_corrupted_copy() {
+        let data: Vec<bool> = (0..30).map(|i| i % 3 == 0).collect();
+        let mut code = encode(&data, 3);
+        let length = code.len() / 3;
+
+        for bit in &mut code[..length] {
+            *bit = !*bit;
+        }
+
+        assert_eq!(decode(&code, data.len(), 3).0, data);
+    }
+}
@@ -0,0 +1,70 @@
+fn order(length: usize, depth: usize) -> Vec<usize> {
+    let depth = depth.clamp(1, length.max(1));
+    let columns = length.div_ceil(depth);
+    let mut order = Vec::with_capacity(length);
+
+    for column in 0..columns {
+        for row in 0..depth {
+            let index = row * columns + column;
+
+            if index < length {
+                order.push(index);
+            }
+        }
+    }
+
+    order
+}
+
+pub fn interleave(data: &[u8], depth: usize) -> Vec<u8> {
+    order(data.len(), depth)
+        .into_iter()
+        .map(|index| data[index])
+        .collect()
+}
+
+pub fn deinterleave(data: &[u8], depth: usize, length: usize) -> Vec<u8> {
+    let mut restored = vec![0u8; length];
+
+    for (position, index) in order(length, depth).into_iter().enumerate() {
+        if let Some(byte) = data.get(position) {
+            restored[index] = *byte;
+        }
+    }
+
+    restored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_ragged_lengths() {
+        for length in [0, 1, 7, 64, 100, 1021] {
+            let data: Vec<u8> = (0..length).map(|i| (i * 37 % 251) as u8).collect();
+
+            for depth in [0, 1, 3, 8, 16, 2000] {
+                let mixed = interleave(&data, depth);
+                assert_eq!(mixed.len(), length);
+                assert_eq!(deinterleave(&mixed, depth, length), data);
+            }
+        }
+    }
+
+    #[test]
+    fn spreads_a_burst() {
+        let data: Vec<u8> = (0..100).collect();
+        let mut mixed = interleave(&data, 10);
+
+        for byte in &mut mixed[20..30] {
+            *byte = 0xFF;
+        }
+
+        let restored = deinterleave(&mixed, 10, data.len());
+        let damaged: Vec<usize> = (0..100).filter(|&i| restored[i] != data[i]).collect();
+
+        assert_eq!(damaged.len(), 10);
+        assert!(damaged.windows(2).all(|pair| pair[1] - pair[0] >= 10));
+    }
+}
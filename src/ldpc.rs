@@ -0,0 +1,220 @@
+use std::sync::OnceLock;
+
+use crate::prng::Rng;
+
+pub const DATA_BITS: usize = 1024;
+
+const PARITY_BITS: usize = 1024;
+
+pub const CODE_BITS: usize = DATA_BITS + PARITY_BITS;
+
+const COLUMN_WEIGHT: usize = 3;
+
+const ITERATIONS: usize = 50;
+
+const SCALE: f64 = 0.75;
+
+struct Code {
+    checks: Vec<Vec<usize>>,
+}
+
+fn code() -> &'static Code {
+    static CODE: OnceLock<Code> = OnceLock::new();
+
+    CODE.get_or_init(|| {
+        let mut rng = Rng::new(0x1D9C);
+        let mut checks = vec![Vec::new(); PARITY_BITS];
+
+        for bit in 0..DATA_BITS {
+            let mut chosen = Vec::new();
+
+            while chosen.len() < COLUMN_WEIGHT {
+                let check = (rng.next_u64() % PARITY_BITS as u64) as usize;
+
+                if !chosen.contains(&check) {
+                    chosen.push(check);
+                }
+            }
+
+            for check in chosen {
+                checks[check].push(bit);
+            }
+        }
+
+        for (i, check) in checks.iter_mut().enumerate() {
+            if i > 0 {
+                check.push(DATA_BITS + i - 1);
+            }
+            check.push(DATA_BITS + i);
+        }
+
+        Code { checks }
+    })
+}
+
+pub fn encode(data: &[bool]) -> Vec<bool> {
+    let code = code();
+    let mut encoded = Vec::with_capacity(data.len().div_ceil(DATA_BITS) * CODE_BITS);
+
+    for chunk in data.chunks(DATA_BITS) {
+        let mut block = chunk.to_vec();
+        block.resize(DATA_BITS, false);
+
+        let mut parity = Vec::with_capacity(PARITY_BITS);
+        let mut accumulator = false;
+
+        for check in &code.checks {
+            for bit in check.iter().filter(|bit| **bit < DATA_BITS) {
+                accumulator ^= block[*bit];
+            }
+            parity.push(accumulator);
+        }
+
+        encoded.extend(block);
+        encoded.extend(parity);
+    }
+
+    encoded
+}
+
+fn decode_block(channel: &[f64]) -> (Vec<bool>, bool) {
+    let code = code();
+
+    let mut variable_edges = vec![Vec::new(); CODE_BITS];
+    let mut edge_variables = Vec::new();
+
+    for check in &code.checks {
+        for bit in check {
+            variable_edges[*bit].push(edge_variables.len());
+            edge_variables.push(*bit);
+        }
+    }
+
+    let mut to_check: Vec<f64> = edge_variables.iter().map(|bit| channel[*bit]).collect();
+    let mut to_variable = vec![0.0; edge_variables.len()];
+    let mut hard: Vec<bool> = channel.iter().map(|llr| *llr < 0.0).collect();
+
+    for _ in 0..ITERATIONS {
+        let mut edge = 0;
+
+        for check in &code.checks {
+            let edges = edge..edge + check.len();
+            edge += check.len();
+
+            let mut sign = 1.0;
+            let mut min = f64::MAX;
+            let mut second = f64::MAX;
+            let mut min_edge = edges.start;
+
+            for e in edges.clone() {
+                let value = to_check[e];
+                if value < 0.0 {
+                    sign = -sign;
+                }
+
+                let magnitude = value.abs();
+                if magnitude < min {
+                    second = min;
+                    min = magnitude;
+                    min_edge = e;
+                } else if magnitude < second {
+                    second = magnitude;
+                }
+            }
+
+            for e in edges {
+                let own_sign = if to_check[e] < 0.0 { -1.0 } else { 1.0 };
+                let magnitude = if e == min_edge { second } else { min };
+                to_variable[e] = SCALE * sign * own_sign * magnitude;
+            }
+        }
+
+        for (bit, edges) in variable_edges.iter().enumerate() {
+            let total = channel[bit] + edges.iter().map(|e| to_variable[*e]).sum::<f64>();
+
+            for e in edges {
+                to_check[*e] = total - to_variable[*e];
+            }
+            hard[bit] = total < 0.0;
+        }
+
+        let satisfied = code
+            .checks
+            .iter()
+            .all(|check| !check.iter().fold(false, |parity, bit| parity ^ hard[*bit]));
+
+        if satisfied {
+            hard.truncate(DATA_BITS);
+            return (hard, true);
+        }
+    }
+
+    hard.truncate(DATA_BITS);
+    (hard, false)
+}
+
+pub fn decode(soft: &[f64], data_len: usize) -> (Vec<bool>, usize) {
+    let mut data = Vec::with_capacity(data_len);
+    let mut failed = 0;
+
+    for block in 0..data_len.div_ceil(DATA_BITS) {
+        let channel: Vec<f64> = (0..CODE_BITS)
+            .map(|i| -soft.get(block * CODE_BITS + i).copied().unwrap_or(0.0))
+            .collect();
+
+        let (bits, converged) = decode_block(&channel);
+        if !converged {
+            failed += 1;
+        }
+
+        data.extend(bits);
+    }
+
+    data.truncate(data_len);
+    (data, failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prng::Rng;
+
+    fn channel(code: &[bool], sigma: f64, rng: &mut Rng) -> Vec<f64> {
+        code.iter()
+            .map(|bit| if *bit { 1.0 } else { -1.0 } + sigma * rng.gaussian())
+            .collect()
+    }
+
+    #[test]
+    fn decodes_clean_blocks() {
+        let mut rng = Rng::new(2);
+        let data: Vec<bool> = (0..DATA_BITS + 100)
+            .map(|_| rng.next_u64() & 1 == 1)
+            .collect();
+        let code = encode(&data);
+
+        assert_eq!(code.len(), 2 * CODE_BITS);
+        assert_eq!(
+            decode(&channel(&code, 0.0, &mut rng), data.len()),
+            (data, 0)
+        );
+    }
+
+    #[test]
+    fn decodes_noisy_soft_values() {
+        let mut rng = Rng::new(3);
+        let data: Vec<bool> = (0..DATA_BITS * 4)
+            .map(|_| rng.next_u64() & 1 == 1)
+            .collect();
+        let soft = channel(&encode(&data), 0.6, &mut rng);
+
+        let hard_errors = soft
+            .iter()
+            .zip(encode(&data))
+            .filter(|(value, bit)| (**value > 0.0) != *bit)
+            .count();
+        assert!(hard_errors > 0);
+
+        assert_eq!(decode(&soft, data.len()), (data, 0));
+    }
+}
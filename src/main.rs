@@ -1,10 +1,38 @@
 mod args;
+mod audio;
+mod autotune;
+mod base64;
+mod calibration;
+mod dct;
 mod ethcer;
+mod fec;
+mod ffmpeg;
+mod gif;
+mod golay;
+mod hash;
+mod interleave;
+mod ldpc;
+mod overlay;
+mod paper;
+mod png;
+mod presets;
+mod prng;
+mod qr;
+mod quantize;
+mod registration;
 mod settings;
+mod simulate;
 mod source;
+mod stego;
+mod still;
+mod subtitles;
 mod tasks;
 mod timer;
+mod titlecard;
 mod ui;
+mod webp;
+mod whiten;
+mod ycbcr;
 
 use anyhow::Ok;
 use clap::Parser;
@@ -0,0 +1,104 @@
+use anyhow::anyhow;
+
+use opencv::core::{CV_8UC3, Mat, Point, Rect, Scalar, Size};
+use opencv::prelude::*;
+use opencv::{imgcodecs, imgproc};
+
+use crate::registration;
+use crate::settings::Settings;
+
+pub fn render(settings: &Settings) -> anyhow::Result<Option<Mat>> {
+    if settings.overlay_image.is_none() && settings.overlay_text.is_none() {
+        return Ok(None);
+    }
+
+    let area = registration::data_area(settings.width, settings.height);
+    let region = registration::embed_area(settings.width, settings.height, settings.region);
+
+    let mut background =
+        Mat::new_rows_cols_with_default(area.height, area.width, CV_8UC3, Scalar::all(0.0))?;
+
+    if let Some(path) = &settings.overlay_image {
+        let image = imgcodecs::imread(path, imgcodecs::IMREAD_COLOR)?;
+
+        if image.empty() {
+            return Err(anyhow!("Failed to read overlay image '{}'", path));
+        }
+
+        imgproc::resize(
+            &image,
+            &mut background,
+            Size::new(area.width, area.height),
+            0.0,
+            0.0,
+            imgproc::INTER_AREA,
+        )?;
+    }
+
+    if let Some(text) = &settings.overlay_text {
+        draw_text(&mut background, text, free_strip(area, region))?;
+    }
+
+    Ok(Some(background))
+}
+
+fn free_strip(area: Rect, region: Rect) -> Rect {
+    let x = region.x - area.x;
+    let y = region.y - area.y;
+
+    let strips = [
+        Rect::new(0, 0, area.width, y),
+        Rect::new(
+            0,
+            y + region.height,
+            area.width,
+            area.height - y - region.height,
+        ),
+        Rect::new(0, 0, x, area.height),
+        Rect::new(
+            x + region.width,
+            0,
+            area.width - x - region.width,
+            area.height,
+        ),
+    ];
+
+    strips
+        .into_iter()
+        .max_by_key(|strip| strip.width * strip.height)
+        .unwrap()
+}
+
+fn draw_text(background: &mut Mat, text: &str, strip: Rect) -> anyhow::Result<()> {
+    if strip.width <= 0 || strip.height <= 0 {
+        return Err(anyhow!("The data region leaves no room for overlay text"));
+    }
+
+    let font = imgproc::FONT_HERSHEY_SIMPLEX;
+    let thickness = (strip.height / 20).max(1);
+    let mut baseline = 0;
+
+    let unit = imgproc::get_text_size(text, font, 1.0, thickness, &mut baseline)?;
+    let scale = (strip.width as f64 * 0.9 / unit.width as f64)
+        .min(strip.height as f64 * 0.6 / unit.height as f64);
+    let size = imgproc::get_text_size(text, font, scale, thickness, &mut baseline)?;
+
+    let origin = Point::new(
+        strip.x + (strip.width - size.width) / 2,
+        strip.y + (strip.height + size.height) / 2,
+    );
+
+    imgproc::put_text(
+        background,
+        text,
+        origin,
+        font,
+        scale,
+        Scalar::all(255.0),
+        thickness,
+        imgproc::LINE_AA,
+        false,
+    )?;
+
+    Ok(())
+}
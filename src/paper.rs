@@ -0,0 +1,335 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+
+use opencv::core::{CV_8UC3, Mat, Point, Point2f, Rect, Scalar, Size, Vector};
+use opencv::prelude::*;
+use opencv::{imgcodecs, imgproc};
+
+use crate::png;
+use crate::still;
+
+// A4 landscape at 300 DPI
+const PAGE_WIDTH: i32 = 3508;
+
+const PAGE_HEIGHT: i32 = 2480;
+
+const PAGE_POINTS: (i32, i32) = (842, 595);
+
+const MARGIN: i32 = 200;
+
+const GAP: i32 = 48;
+
+const BORDER: i32 = 12;
+
+const SCAN_INSET: f64 = 0.015;
+
+fn render_page(frame: &Mat, number: usize, count: usize) -> anyhow::Result<Mat> {
+    let mut page =
+        Mat::new_rows_cols_with_default(PAGE_HEIGHT, PAGE_WIDTH, CV_8UC3, Scalar::all(255.0))?;
+
+    let room_width = (PAGE_WIDTH - 2 * (MARGIN + GAP)) as f64;
+    let room_height = (PAGE_HEIGHT - 3 * MARGIN - 2 * GAP) as f64;
+    let scale = (room_width / frame.cols() as f64).min(room_height / frame.rows() as f64);
+    let size = Size::new(
+        (frame.cols() as f64 * scale) as i32,
+        (frame.rows() as f64 * scale) as i32,
+    );
+
+    let mut scaled = Mat::default();
+    imgproc::resize(frame, &mut scaled, size, 0.0, 0.0, imgproc::INTER_NEAREST)?;
+
+    let area = Rect::new(
+        (PAGE_WIDTH - size.width) / 2,
+        MARGIN + GAP,
+        size.width,
+        size.height,
+    );
+
+    {
+        let mut target = Mat::roi_mut(&mut page, area)?;
+        scaled.copy_to(&mut target)?;
+    }
+
+    imgproc::rectangle(
+        &mut page,
+        Rect::new(
+            area.x - GAP,
+            area.y - GAP,
+            area.width + 2 * GAP,
+            area.height + 2 * GAP,
+        ),
+        Scalar::all(0.0),
+        BORDER,
+        imgproc::LINE_8,
+        0,
+    )?;
+
+    let label = format!("Page {} / {}", number, count);
+    let font = imgproc::FONT_HERSHEY_SIMPLEX;
+    let mut baseline = 0;
+    let text = imgproc::get_text_size(&label, font, 2.0, 4, &mut baseline)?;
+
+    imgproc::put_text(
+        &mut page,
+        &label,
+        Point::new((PAGE_WIDTH - text.width) / 2, PAGE_HEIGHT - MARGIN),
+        font,
+        2.0,
+        Scalar::all(0.0),
+        4,
+        imgproc::LINE_AA,
+        false,
+    )?;
+
+    Ok(page)
+}
+
+fn render_pages(frames: &[Mat]) -> anyhow::Result<Vec<Mat>> {
+    frames
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| render_page(frame, i + 1, frames.len()))
+        .collect()
+}
+
+pub fn write_pngs(dir: &str, frames: &[Mat]) -> anyhow::Result<()> {
+    fs::create_dir_all(dir)?;
+
+    for (i, page) in render_pages(frames)?.iter().enumerate() {
+        let path = Path::new(dir).join(format!("page-{:04}.png", i + 1));
+        imgcodecs::imwrite_def(&path.to_string_lossy(), page)?;
+    }
+
+    println!("{} pages written to {}", frames.len(), dir);
+    Ok(())
+}
+
+fn pdf_object(pdf: &mut Vec<u8>, offsets: &mut Vec<usize>, body: &[u8]) {
+    offsets.push(pdf.len());
+    pdf.extend(format!("{} 0 obj\n", offsets.len()).as_bytes());
+    pdf.extend(body);
+    pdf.extend(b"\nendobj\n");
+}
+
+fn pdf_stream(dictionary: &str, data: &[u8]) -> Vec<u8> {
+    let mut body = format!("<< {} /Length {} >>\nstream\n", dictionary, data.len()).into_bytes();
+    body.extend(data);
+    body.extend(b"\nendstream");
+    body
+}
+
+pub fn write_pdf(path: &str, frames: &[Mat]) -> anyhow::Result<()> {
+    let pages = render_pages(frames)?;
+    let (page_width, page_height) = PAGE_POINTS;
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+
+    let kids = (0..pages.len())
+        .map(|i| format!("{} 0 R", 3 + 3 * i))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    pdf_object(&mut pdf, &mut offsets, b"<< /Type /Catalog /Pages 2 0 R >>");
+    pdf_object(
+        &mut pdf,
+        &mut offsets,
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids, pages.len()).as_bytes(),
+    );
+
+    for (i, page) in pages.iter().enumerate() {
+        let image_object = 4 + 3 * i;
+        let content_object = 5 + 3 * i;
+
+        // PNG image data is a zlib stream with PNG predictors, which PDF decodes natively
+        let mut encoded = Vector::<u8>::new();
+        imgcodecs::imencode_def(".png", page, &mut encoded)?;
+        let image = png::rgb_data(encoded.as_slice())?;
+
+        pdf_object(
+            &mut pdf,
+            &mut offsets,
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /XObject << /Im0 {} 0 R >> >> /Contents {} 0 R >>",
+                page_width, page_height, image_object, content_object
+            )
+            .as_bytes(),
+        );
+        pdf_object(
+            &mut pdf,
+            &mut offsets,
+            &pdf_stream(
+                &format!(
+                    "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /FlateDecode /DecodeParms << /Predictor 15 /Colors 3 /BitsPerComponent 8 /Columns {} >>",
+                    page.cols(),
+                    page.rows(),
+                    page.cols()
+                ),
+                &image,
+            ),
+        );
+        pdf_object(
+            &mut pdf,
+            &mut offsets,
+            &pdf_stream(
+                "",
+                format!("q {} 0 0 {} 0 0 cm /Im0 Do Q", page_width, page_height).as_bytes(),
+            ),
+        );
+    }
+
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1).as_bytes());
+    for offset in &offsets {
+        pdf.extend(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            offsets.len() + 1,
+            xref
+        )
+        .as_bytes(),
+    );
+
+    fs::write(path, pdf)?;
+    println!("{} pages written to {}", pages.len(), path);
+    Ok(())
+}
+
+fn corners(quad: &Vector<Point>) -> [Point2f; 4] {
+    let points: Vec<Point2f> = quad
+        .iter()
+        .map(|p| Point2f::new(p.x as f32, p.y as f32))
+        .collect();
+
+    let by = |key: fn(&Point2f) -> f32, largest: bool| {
+        let mut sorted = points.clone();
+        sorted.sort_by(|a, b| key(a).total_cmp(&key(b)));
+        if largest { sorted[3] } else { sorted[0] }
+    };
+
+    [
+        by(|p| p.x + p.y, false),
+        by(|p| p.x - p.y, true),
+        by(|p| p.x - p.y, false),
+        by(|p| p.x + p.y, true),
+    ]
+}
+
+fn distance(a: Point2f, b: Point2f) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+fn deskew(scan: &Mat) -> anyhow::Result<Mat> {
+    let mut gray = Mat::default();
+    imgproc::cvt_color_def(scan, &mut gray, imgproc::COLOR_BGR2GRAY)?;
+
+    let mut binary = Mat::default();
+    imgproc::threshold(
+        &gray,
+        &mut binary,
+        0.0,
+        255.0,
+        imgproc::THRESH_BINARY_INV | imgproc::THRESH_OTSU,
+    )?;
+
+    let mut contours = Vector::<Vector<Point>>::new();
+    imgproc::find_contours_def(
+        &binary,
+        &mut contours,
+        imgproc::RETR_EXTERNAL,
+        imgproc::CHAIN_APPROX_SIMPLE,
+    )?;
+
+    let mut largest = None;
+    let mut largest_area = 0.0;
+
+    for contour in contours {
+        let area = imgproc::contour_area_def(&contour)?;
+        if area > largest_area {
+            largest_area = area;
+            largest = Some(contour);
+        }
+    }
+
+    let Some(border) = largest else {
+        return Err(anyhow!("No page border found in scan"));
+    };
+
+    let mut quad = Vector::<Point>::new();
+    let epsilon = imgproc::arc_length(&border, true)? * 0.02;
+    imgproc::approx_poly_dp(&border, &mut quad, epsilon, true)?;
+
+    if quad.len() != 4 {
+        return Err(anyhow!("Page border in scan is not a quadrilateral"));
+    }
+
+    let [top_left, top_right, bottom_left, bottom_right] = corners(&quad);
+    let width = distance(top_left, top_right).max(distance(bottom_left, bottom_right));
+    let height = distance(top_left, bottom_left).max(distance(top_right, bottom_right));
+
+    let source = Vector::from_slice(&[top_left, top_right, bottom_left, bottom_right]);
+    let target = Vector::from_slice(&[
+        Point2f::new(0.0, 0.0),
+        Point2f::new(width, 0.0),
+        Point2f::new(0.0, height),
+        Point2f::new(width, height),
+    ]);
+    let transform = imgproc::get_perspective_transform_def(&source, &target)?;
+
+    let mut page = Mat::default();
+    imgproc::warp_perspective_def(
+        scan,
+        &mut page,
+        &transform,
+        Size::new(width as i32, height as i32),
+    )?;
+
+    let inset = (width.min(height) as f64 * SCAN_INSET) as i32;
+    let inner = Rect::new(
+        inset,
+        inset,
+        width as i32 - 2 * inset,
+        height as i32 - 2 * inset,
+    );
+
+    Ok(Mat::roi(&page, inner)?.try_clone()?)
+}
+
+pub fn read_scans(path: &str) -> anyhow::Result<Vec<Mat>> {
+    let mut files: Vec<PathBuf> = if Path::new(path).is_dir() {
+        fs::read_dir(path)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| still::is_image(&file.to_string_lossy()))
+            .collect()
+    } else {
+        vec![PathBuf::from(path)]
+    };
+    files.sort();
+
+    let mut pages = Vec::new();
+
+    for file in files {
+        let scan = imgcodecs::imread(&file.to_string_lossy(), imgcodecs::IMREAD_COLOR)?;
+
+        if scan.empty() {
+            println!("Skipping unreadable scan {}", file.display());
+            continue;
+        }
+
+        match deskew(&scan) {
+            Ok(page) => pages.push(page),
+            Err(e) => println!("Skipping scan {}: {}", file.display(), e),
+        }
+    }
+
+    if pages.is_empty() {
+        return Err(anyhow!("No readable scans found at {}", path));
+    }
+
+    println!("Deskewed {} scanned pages", pages.len());
+    Ok(pages)
+}
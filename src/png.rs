@@ -0,0 +1,166 @@
+use anyhow::anyhow;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+pub fn chunks(bytes: &[u8]) -> anyhow::Result<Vec<([u8; 4], &[u8])>> {
+    if !bytes.starts_with(&SIGNATURE) {
+        return Err(anyhow!("Not a PNG file"));
+    }
+
+    let mut chunks = Vec::new();
+    let mut offset = SIGNATURE.len();
+
+    while offset < bytes.len() {
+        if offset + 12 > bytes.len() {
+            return Err(anyhow!("Truncated PNG chunk"));
+        }
+
+        let size = u32::from_be_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ]) as usize;
+        let tag = [
+            bytes[offset + 4],
+            bytes[offset + 5],
+            bytes[offset + 6],
+            bytes[offset + 7],
+        ];
+
+        if offset + 12 + size > bytes.len() {
+            return Err(anyhow!("Truncated PNG chunk"));
+        }
+
+        chunks.push((tag, &bytes[offset + 8..offset + 8 + size]));
+        offset += 12 + size;
+    }
+
+    Ok(chunks)
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(u32::MAX, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| {
+            (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+fn chunk(tag: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut bytes = (payload.len() as u32).to_be_bytes().to_vec();
+    bytes.extend_from_slice(tag);
+    bytes.extend_from_slice(payload);
+    bytes.extend_from_slice(&crc32(&bytes[4..]).to_be_bytes());
+    bytes
+}
+
+pub fn with_text(bytes: &[u8], key: &str, value: &str) -> anyhow::Result<Vec<u8>> {
+    let (tag, header) = *chunks(bytes)?
+        .first()
+        .ok_or_else(|| anyhow!("PNG has no header"))?;
+
+    if &tag != b"IHDR" {
+        return Err(anyhow!("PNG does not start with a header"));
+    }
+
+    let mut payload = key.as_bytes().to_vec();
+    payload.push(0);
+    payload.extend_from_slice(value.as_bytes());
+
+    let split = SIGNATURE.len() + 12 + header.len();
+    let mut out = bytes[..split].to_vec();
+    out.extend(chunk(b"tEXt", &payload));
+    out.extend_from_slice(&bytes[split..]);
+    Ok(out)
+}
+
+pub fn text(bytes: &[u8], key: &str) -> Option<String> {
+    chunks(bytes)
+        .ok()?
+        .into_iter()
+        .filter(|(tag, _)| tag == b"tEXt")
+        .find_map(|(_, payload)| {
+            let (name, value) = payload.split_at(payload.iter().position(|b| *b == 0)?);
+            (name == key.as_bytes()).then(|| String::from_utf8_lossy(&value[1..]).to_string())
+        })
+}
+
+// Returns the zlib stream of an 8 bit RGB, non-interlaced PNG
+pub fn rgb_data(bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let chunks = chunks(bytes)?;
+
+    let header = chunks
+        .iter()
+        .find(|(tag, _)| tag == b"IHDR")
+        .map(|(_, payload)| *payload)
+        .filter(|payload| payload.len() == 13)
+        .ok_or_else(|| anyhow!("PNG has no header"))?;
+
+    if header[8] != 8 || header[9] != 2 || header[12] != 0 {
+        return Err(anyhow!("PNG is not 8 bit RGB without interlacing"));
+    }
+
+    Ok(chunks
+        .iter()
+        .filter(|(tag, _)| tag == b"IDAT")
+        .flat_map(|(_, payload)| payload.iter().copied())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(color_type: u8, idat: &[&[u8]]) -> Vec<u8> {
+        let mut bytes = SIGNATURE.to_vec();
+        bytes.extend(chunk(
+            b"IHDR",
+            &[0, 0, 0, 4, 0, 0, 0, 2, 8, color_type, 0, 0, 0],
+        ));
+        for part in idat {
+            bytes.extend(chunk(b"IDAT", part));
+        }
+        bytes.extend(chunk(b"IEND", &[]));
+        bytes
+    }
+
+    #[test]
+    fn joins_image_data_chunks() {
+        let bytes = image(2, &[b"zlib", b"", b"stream"]);
+
+        assert_eq!(chunks(&bytes).unwrap().len(), 5);
+        assert_eq!(rgb_data(&bytes).unwrap(), b"zlibstream");
+    }
+
+    #[test]
+    fn rejects_other_layouts() {
+        assert!(rgb_data(&image(6, &[b"data"])).is_err());
+        assert!(rgb_data(b"GIF89a").is_err());
+
+        let bytes = image(2, &[b"data"]);
+        assert!(chunks(&bytes[..bytes.len() - 20]).is_err());
+    }
+
+    #[test]
+    fn crc_matches_reference_values() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+    }
+
+    #[test]
+    fn text_chunk_round_trips() {
+        let bytes = with_text(&image(2, &[b"data"]), "frame-height", "1080").unwrap();
+        let tags: Vec<[u8; 4]> = chunks(&bytes)
+            .unwrap()
+            .iter()
+            .map(|(tag, _)| *tag)
+            .collect();
+
+        assert_eq!(tags, [*b"IHDR", *b"tEXt", *b"IDAT", *b"IEND"]);
+        assert_eq!(text(&bytes, "frame-height").as_deref(), Some("1080"));
+        assert_eq!(text(&bytes, "other"), None);
+        assert_eq!(rgb_data(&bytes).unwrap(), b"data");
+    }
+}
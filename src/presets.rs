@@ -0,0 +1,41 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{OutputMode, Settings};
+
+const PRESETS_PATH: &str = "presets.json";
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedPreset {
+    pub mode: OutputMode,
+
+    pub settings: Settings,
+}
+
+fn load_all() -> anyhow::Result<BTreeMap<String, SavedPreset>> {
+    if !Path::new(PRESETS_PATH).exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let contents = std::fs::read_to_string(PRESETS_PATH)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+pub fn load(name: &str) -> anyhow::Result<SavedPreset> {
+    load_all()?
+        .remove(name)
+        .ok_or_else(|| anyhow!("No saved preset named '{}' in {}", name, PRESETS_PATH))
+}
+
+pub fn save(name: &str, preset: SavedPreset) -> anyhow::Result<()> {
+    let mut presets = load_all()?;
+    presets.insert(name.to_string(), preset);
+
+    std::fs::write(PRESETS_PATH, serde_json::to_string_pretty(&presets)?)?;
+    println!("Saved preset '{}' to {}", name, PRESETS_PATH);
+
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use anyhow::anyhow;
+
+use opencv::core::{CV_8UC3, Mat, Rect, Scalar, Size, Vector};
+use opencv::imgproc;
+use opencv::objdetect::{
+    QRCodeDetector, QRCodeEncoder, QRCodeEncoder_EncodeMode, QRCodeEncoder_Params,
+};
+use opencv::prelude::*;
+
+use crate::base64;
+use crate::settings::Settings;
+
+pub const CHUNK_BYTES: usize = 1024;
+
+const SYMBOLS: i32 = 4;
+
+const PREFIX: &str = "vidvault";
+
+pub fn etch_frames(bytes: &[u8], settings: &Settings) -> anyhow::Result<Vec<Mat>> {
+    let count = bytes.len().div_ceil(CHUNK_BYTES).max(1);
+    let mut params = QRCodeEncoder_Params::default()?;
+    params.set_mode(QRCodeEncoder_EncodeMode::MODE_STRUCTURED_APPEND);
+    params.set_structure_number(SYMBOLS);
+
+    let mut encoder = QRCodeEncoder::create(&params)?;
+    let mut frames = Vec::new();
+
+    for index in 0..count {
+        let chunk = &bytes[index * CHUNK_BYTES..((index + 1) * CHUNK_BYTES).min(bytes.len())];
+        let text = format!(
+            "{} {}/{} {}",
+            PREFIX,
+            index + 1,
+            count,
+            base64::encode(chunk)
+        );
+
+        let mut codes = Vector::<Mat>::new();
+        encoder.encode_structured_append(&text, &mut codes)?;
+
+        frames.push(render(&codes.to_vec(), settings.width, settings.height)?);
+    }
+
+    println!("Encoded {} bytes into {} QR frames", bytes.len(), count);
+    Ok(frames)
+}
+
+fn render(codes: &[Mat], width: i32, height: i32) -> anyhow::Result<Mat> {
+    let mut canvas = Mat::new_rows_cols_with_default(height, width, CV_8UC3, Scalar::all(255.0))?;
+
+    let count = codes.len().max(1) as i32;
+    let columns = (count as f64).sqrt().ceil() as i32;
+    let rows = (count + columns - 1) / columns;
+    let cell = (width / columns).min(height / rows);
+    let side = cell * 9 / 10;
+    let left = (width - columns * cell) / 2;
+    let top = (height - rows * cell) / 2;
+
+    for (index, code) in codes.iter().enumerate() {
+        let (column, row) = (index as i32 % columns, index as i32 / columns);
+
+        let mut scaled = Mat::default();
+        imgproc::resize(
+            code,
+            &mut scaled,
+            Size::new(side, side),
+            0.0,
+            0.0,
+            imgproc::INTER_NEAREST,
+        )?;
+
+        let mut colored = Mat::default();
+        imgproc::cvt_color_def(&scaled, &mut colored, imgproc::COLOR_GRAY2BGR)?;
+
+        let mut area = Mat::roi_mut(
+            &mut canvas,
+            Rect::new(
+                left + column * cell + (cell - side) / 2,
+                top + row * cell + (cell - side) / 2,
+                side,
+                side,
+            ),
+        )?;
+        colored.copy_to(&mut area)?;
+    }
+
+    Ok(canvas)
+}
+
+fn parse(text: &str) -> Option<(usize, usize, Vec<u8>)> {
+    let mut parts = text.splitn(3, ' ');
+
+    if parts.next()? != PREFIX {
+        return None;
+    }
+
+    let (index, count) = parts.next()?.split_once('/')?;
+    let data = base64::decode(parts.next()?).ok()?;
+
+    Some((index.parse().ok()?, count.parse().ok()?, data))
+}
+
+pub fn read(frames: impl Iterator<Item = Mat>) -> anyhow::Result<Option<Vec<u8>>> {
+    let detector = QRCodeDetector::default()?;
+    let mut chunks: Vec<Option<Vec<u8>>> = Vec::new();
+
+    for frame in frames {
+        let mut decoded = Vector::<String>::new();
+        detector.detect_and_decode_multi_def(&frame, &mut decoded)?;
+
+        // Structured append groups come back joined in sequence order, lone parts don't parse
+        let Some((index, count, chunk)) = decoded.iter().find_map(|text| parse(&text)) else {
+            continue;
+        };
+
+        if chunks.is_empty() {
+            chunks.resize(count, None);
+        }
+
+        if count != chunks.len() || index == 0 || index > count {
+            continue;
+        }
+
+        if chunks[index - 1].is_none() {
+            println!("Read QR frame {}/{}", index, count);
+            chunks[index - 1] = Some(chunk);
+        }
+    }
+
+    if chunks.is_empty() {
+        return Ok(None);
+    }
+
+    let missing = chunks.iter().filter(|chunk| chunk.is_none()).count();
+
+    if missing > 0 {
+        return Err(anyhow!(
+            "{} of {} QR frames could not be read",
+            missing,
+            chunks.len()
+        ));
+    }
+
+    Ok(Some(chunks.into_iter().flatten().flatten().collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_frame_text() {
+        let text = format!("{} 3/7 {}", PREFIX, base64::encode(b"chunk"));
+        assert_eq!(parse(&text), Some((3, 7, b"chunk".to_vec())));
+
+        assert_eq!(parse("other 1/1 Zm9v"), None);
+        assert_eq!(parse("vidvault 1-1 Zm9v"), None);
+        assert_eq!(parse("vidvault 1/1 Zm9v!"), None);
+    }
+
+    #[test]
+    fn multi_symbol_frames_round_trip() {
+        let mut rng = crate::prng::Rng::new(3);
+        let payload: Vec<u8> = (0..CHUNK_BYTES * 3 + 100)
+            .map(|_| rng.next_u64() as u8)
+            .collect();
+        let settings = Settings::new(8, 1, 30, 1280, 720);
+
+        let frames = etch_frames(&payload, &settings).unwrap();
+        assert_eq!(frames.len(), 4);
+
+        assert_eq!(read(frames.into_iter()).unwrap(), Some(payload));
+    }
+}
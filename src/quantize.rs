@@ -0,0 +1,109 @@
+pub fn levels(bits: u8) -> Vec<u8> {
+    levels_between(bits, 0, 255)
+}
+
+pub fn levels_between(bits: u8, low: u8, high: u8) -> Vec<u8> {
+    let count = 1u32 << bits;
+    let span = (high - low) as u32;
+
+    (0..count)
+        .map(|i| low + (i * span / (count - 1)) as u8)
+        .collect()
+}
+
+fn gray_encode(value: u8) -> u8 {
+    value ^ (value >> 1)
+}
+
+fn gray_decode(mut value: u8) -> u8 {
+    let mut decoded = value;
+    while value > 0 {
+        value >>= 1;
+        decoded ^= value;
+    }
+    decoded
+}
+
+pub fn bits_to_level(bits: &[bool], levels: &[u8]) -> u8 {
+    let value = bits.iter().fold(0u8, |v, b| (v << 1) + (*b as u8));
+    levels[gray_decode(value) as usize]
+}
+
+pub fn level_to_bits(value: f64, levels: &[u8]) -> Vec<bool> {
+    let nearest = levels
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            (value - **a as f64)
+                .abs()
+                .total_cmp(&(value - **b as f64).abs())
+        })
+        .map(|(i, _)| i as u8)
+        .unwrap();
+
+    let bit_count = levels.len().trailing_zeros();
+    let code = gray_encode(nearest);
+
+    (0..bit_count).rev().map(|i| (code >> i) & 1 == 1).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols(bits: u8) -> impl Iterator<Item = Vec<bool>> {
+        (0..1u8 << bits).map(move |value| (0..bits).rev().map(|i| (value >> i) & 1 == 1).collect())
+    }
+
+    #[test]
+    fn bits_round_trip_through_levels() {
+        for bits in 1..=3 {
+            let levels = levels(bits);
+            assert_eq!(levels.len(), 1 << bits);
+            assert_eq!((levels[0], levels[levels.len() - 1]), (0, 255));
+
+            for symbol in symbols(bits) {
+                let level = bits_to_level(&symbol, &levels);
+                assert_eq!(level_to_bits(level as f64, &levels), symbol);
+            }
+        }
+    }
+
+    #[test]
+    fn values_snap_to_the_nearest_level() {
+        for bits in 1..=3 {
+            let levels = levels(bits);
+
+            for pair in levels.windows(2) {
+                let (low, high) = (pair[0] as f64, pair[1] as f64);
+                let middle = (low + high) / 2.0;
+
+                assert_eq!(
+                    level_to_bits(middle - 0.5, &levels),
+                    level_to_bits(low, &levels)
+                );
+                assert_eq!(
+                    level_to_bits(middle + 0.5, &levels),
+                    level_to_bits(high, &levels)
+                );
+            }
+
+            assert_eq!(level_to_bits(-40.0, &levels), level_to_bits(0.0, &levels));
+            assert_eq!(level_to_bits(300.0, &levels), level_to_bits(255.0, &levels));
+        }
+    }
+
+    #[test]
+    fn neighbouring_gray_levels_differ_in_one_bit() {
+        for bits in 1..=3 {
+            let levels = levels_between(bits, 16, 235);
+
+            for pair in levels.windows(2) {
+                let low = level_to_bits(pair[0] as f64, &levels);
+                let high = level_to_bits(pair[1] as f64, &levels);
+
+                assert_eq!(low.iter().zip(&high).filter(|(a, b)| a != b).count(), 1);
+            }
+        }
+    }
+}
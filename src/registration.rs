@@ -0,0 +1,639 @@
+use opencv::core::{self, CV_8UC3, Mat, Point, Point2f, Rect, Scalar, Size, Vec3b, Vec4i, Vector};
+use opencv::imgproc;
+use opencv::prelude::*;
+
+use crate::calibration;
+use crate::golay;
+
+pub const HEADER_WIDTH: i32 = 384;
+
+pub const HEADER_HEIGHT: i32 = 216;
+
+const MARKER_MODULES: i32 = 7;
+
+const TAG_CELLS: usize = 24;
+
+const TAG_BITS: usize = 24;
+
+pub const MAX_TAG_INDEX: u32 = 0x3_FFFF;
+
+const MAX_TAG_CORRECTIONS: usize = 4;
+
+const TAG_CHECK_SALT: u32 = 0x5;
+
+const TAG_START: f32 = 0.15;
+
+const TAG_END: f32 = 0.85;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrameKind {
+    Header,
+    Calibration,
+    Palette,
+    Data,
+}
+
+#[derive(Clone, Copy)]
+pub struct Tag {
+    pub kind: FrameKind,
+
+    pub index: u32,
+}
+
+impl Tag {
+    pub fn new(kind: FrameKind, index: u32) -> Self {
+        Tag { kind, index }
+    }
+
+    fn check(kind: u32, index: u32) -> u32 {
+        (0..18)
+            .step_by(3)
+            .fold(kind ^ TAG_CHECK_SALT, |check, shift| {
+                check ^ (index >> shift)
+            })
+            & 0x7
+    }
+
+    fn bits(&self) -> Vec<bool> {
+        let kind = match self.kind {
+            FrameKind::Header => 0,
+            FrameKind::Calibration => 1,
+            FrameKind::Palette => 2,
+            FrameKind::Data => 3,
+        };
+        let index = self.index & MAX_TAG_INDEX;
+        let value = (kind << 21) | (Self::check(kind, index) << 18) | index;
+        let bits: Vec<bool> = (0..TAG_BITS).rev().map(|i| (value >> i) & 1 == 1).collect();
+
+        let mut code = golay::encode(&bits, 1);
+        code.resize(TAG_CELLS * 2, false);
+        code
+    }
+
+    fn from_bits(code: &[bool]) -> Option<Self> {
+        let (bits, corrected) = golay::decode(code, TAG_BITS, 1);
+        if corrected > MAX_TAG_CORRECTIONS {
+            return None;
+        }
+
+        let value = bits.iter().fold(0u32, |v, b| (v << 1) | *b as u32);
+        let kind = value >> 21;
+        let index = value & MAX_TAG_INDEX;
+
+        if (value >> 18) & 0x7 != Self::check(kind, index) {
+            return None;
+        }
+
+        let kind = match kind {
+            0 => FrameKind::Header,
+            1 => FrameKind::Calibration,
+            2 => FrameKind::Palette,
+            3 => FrameKind::Data,
+            _ => return None,
+        };
+
+        Some(Tag { kind, index })
+    }
+}
+
+fn module(height: i32) -> i32 {
+    (height / 180).max(2)
+}
+
+fn band(height: i32) -> i32 {
+    module(height) * (MARKER_MODULES + 2)
+}
+
+const CODEC_BLOCK: i32 = 16;
+
+pub fn data_area(width: i32, height: i32) -> Rect {
+    // Keep the data on the macroblock grid so block edges fall between cells
+    let left = (band(height) + CODEC_BLOCK - 1) / CODEC_BLOCK * CODEC_BLOCK;
+    Rect::new(left, 0, (width - 2 * left).max(0), height)
+}
+
+pub fn header_area() -> Rect {
+    let band = band(HEADER_HEIGHT);
+    Rect::new(band, 0, HEADER_WIDTH - 2 * band, HEADER_HEIGHT)
+}
+
+pub fn embed_area(width: i32, height: i32, region: [i32; 4]) -> Rect {
+    let area = data_area(width, height);
+    let [x, y, region_width, region_height] = region;
+
+    if region_width <= 0 || region_height <= 0 {
+        return area;
+    }
+
+    let left = x.max(area.x);
+    let top = y.max(area.y);
+    let right = (x + region_width).min(area.x + area.width);
+    let bottom = (y + region_height).min(area.y + area.height);
+
+    Rect::new(left, top, (right - left).max(0), (bottom - top).max(0))
+}
+
+fn marker_origins(width: i32, height: i32) -> [Point; 4] {
+    let module = module(height);
+    let far_x = width - module * (MARKER_MODULES + 1);
+    let far_y = height - module * (MARKER_MODULES + 1);
+
+    [
+        Point::new(module, module),
+        Point::new(far_x, module),
+        Point::new(module, far_y),
+        Point::new(far_x, far_y),
+    ]
+}
+
+fn marker_centers(width: i32, height: i32) -> Vec<Point2f> {
+    let offset = (module(height) * MARKER_MODULES - 1) as f32 / 2.0;
+
+    marker_origins(width, height)
+        .iter()
+        .map(|origin| Point2f::new(origin.x as f32 + offset, origin.y as f32 + offset))
+        .collect()
+}
+
+fn draw_marker(canvas: &mut Mat, origin: Point, module: i32) -> anyhow::Result<()> {
+    for (inset, brightness) in [(0, 0.0), (1, 255.0), (2, 0.0)] {
+        let side = module * (MARKER_MODULES - 2 * inset);
+        let rect = Rect::new(
+            origin.x + module * inset,
+            origin.y + module * inset,
+            side,
+            side,
+        );
+
+        imgproc::rectangle(
+            canvas,
+            rect,
+            Scalar::all(brightness),
+            imgproc::FILLED,
+            imgproc::LINE_8,
+            0,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn tag_span(cell: usize) -> (f32, f32) {
+    let step = (TAG_END - TAG_START) / TAG_CELLS as f32;
+    (
+        TAG_START + step * cell as f32,
+        TAG_START + step * (cell + 1) as f32,
+    )
+}
+
+fn draw_tag(canvas: &mut Mat, tag: Tag, width: i32, height: i32) -> anyhow::Result<()> {
+    let origins = marker_origins(width, height);
+    let centers = marker_centers(width, height);
+    let side = module(height) * MARKER_MODULES;
+
+    let code = tag.bits();
+
+    for (band, (top, bottom)) in [(0, 2), (1, 3)].into_iter().enumerate() {
+        let span = centers[bottom].y - centers[top].y;
+
+        for cell in 0..TAG_CELLS {
+            if !code[band * TAG_CELLS + cell] {
+                continue;
+            }
+
+            let (start, end) = tag_span(cell);
+            let y0 = (centers[top].y + start * span).round() as i32;
+            let y1 = (centers[top].y + end * span).round() as i32;
+
+            imgproc::rectangle(
+                canvas,
+                Rect::new(origins[top].x, y0, side, (y1 - y0).max(1)),
+                Scalar::all(0.0),
+                imgproc::FILLED,
+                imgproc::LINE_8,
+                0,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn compose(
+    data: &Mat,
+    region: Rect,
+    background: Option<&Mat>,
+    width: i32,
+    height: i32,
+    tag: Tag,
+) -> anyhow::Result<Mat> {
+    let mut canvas = Mat::new_rows_cols_with_default(height, width, CV_8UC3, Scalar::all(255.0))?;
+
+    if let Some(background) = background {
+        let mut area = Mat::roi_mut(&mut canvas, data_area(width, height))?;
+        background.copy_to(&mut area)?;
+    }
+
+    {
+        let mut area = Mat::roi_mut(&mut canvas, region)?;
+        data.copy_to(&mut area)?;
+    }
+
+    for origin in marker_origins(width, height) {
+        draw_marker(&mut canvas, origin, module(height))?;
+    }
+
+    draw_tag(&mut canvas, tag, width, height)?;
+
+    Ok(canvas)
+}
+
+pub fn compose_header(data: &Mat, width: i32, height: i32) -> anyhow::Result<Mat> {
+    let tag = Tag::new(FrameKind::Header, 0);
+    let region = header_area();
+    let canvas = compose(data, region, None, HEADER_WIDTH, HEADER_HEIGHT, tag)?;
+
+    let mut frame = Mat::default();
+    imgproc::resize(
+        &canvas,
+        &mut frame,
+        Size::new(width, height),
+        0.0,
+        0.0,
+        imgproc::INTER_NEAREST,
+    )?;
+
+    Ok(frame)
+}
+
+fn find_markers(frame: &Mat, capture: bool) -> anyhow::Result<Vec<Point2f>> {
+    let mut gray = Mat::default();
+    imgproc::cvt_color_def(frame, &mut gray, imgproc::COLOR_BGR2GRAY)?;
+
+    let mut binary = Mat::default();
+
+    if capture {
+        let block_size = (gray.rows() / 20).max(5) | 1;
+        imgproc::adaptive_threshold(
+            &gray,
+            &mut binary,
+            255.0,
+            imgproc::ADAPTIVE_THRESH_GAUSSIAN_C,
+            imgproc::THRESH_BINARY_INV,
+            block_size,
+            5.0,
+        )?;
+    } else {
+        imgproc::threshold(
+            &gray,
+            &mut binary,
+            0.0,
+            255.0,
+            imgproc::THRESH_BINARY_INV | imgproc::THRESH_OTSU,
+        )?;
+    }
+
+    let mut contours = Vector::<Vector<Point>>::new();
+    let mut hierarchy = Vector::<Vec4i>::new();
+    imgproc::find_contours_with_hierarchy_def(
+        &binary,
+        &mut contours,
+        &mut hierarchy,
+        imgproc::RETR_TREE,
+        imgproc::CHAIN_APPROX_SIMPLE,
+    )?;
+
+    let mut centers = Vec::new();
+
+    for i in 0..contours.len() {
+        let child = hierarchy.get(i)?[2];
+        if child < 0 {
+            continue;
+        }
+
+        let grandchild = hierarchy.get(child as usize)?[2];
+        if grandchild < 0 {
+            continue;
+        }
+
+        let outer = contours.get(i)?;
+        let inner = contours.get(grandchild as usize)?;
+
+        let rect = imgproc::bounding_rect(&outer)?;
+        let aspect = rect.width as f64 / rect.height.max(1) as f64;
+        if !(0.7..=1.4).contains(&aspect) {
+            continue;
+        }
+
+        let inner_area = imgproc::contour_area_def(&inner)?;
+        if inner_area <= 0.0 {
+            continue;
+        }
+
+        let ratio = imgproc::contour_area_def(&outer)? / inner_area;
+        if !(2.5..=12.0).contains(&ratio) {
+            continue;
+        }
+
+        let moments = imgproc::moments_def(&outer)?;
+        if moments.m00 <= 0.0 {
+            continue;
+        }
+
+        centers.push(Point2f::new(
+            (moments.m10 / moments.m00) as f32,
+            (moments.m01 / moments.m00) as f32,
+        ));
+    }
+
+    Ok(centers)
+}
+
+pub fn locate(frame: &Mat, capture: bool) -> anyhow::Result<Option<Vec<Point2f>>> {
+    let candidates = find_markers(frame, capture)?;
+
+    let width = frame.cols() as f32;
+    let height = frame.rows() as f32;
+    let corners = [(0.0, 0.0), (width, 0.0), (0.0, height), (width, height)];
+    let reach = (width * width + height * height).sqrt() / 5.0;
+
+    let mut markers = Vec::new();
+
+    for (corner_x, corner_y) in corners {
+        let nearest = candidates
+            .iter()
+            .map(|c| {
+                (
+                    c,
+                    ((c.x - corner_x).powi(2) + (c.y - corner_y).powi(2)).sqrt(),
+                )
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        match nearest {
+            Some((center, distance)) if distance < reach => markers.push(*center),
+            _ => return Ok(None),
+        }
+    }
+
+    Ok(Some(markers))
+}
+
+fn sample(frame: &Mat, point: Point2f) -> anyhow::Result<f64> {
+    let x = point.x.round() as i32;
+    let y = point.y.round() as i32;
+
+    let mut sum = 0.0;
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let bgr = frame.at_2d::<Vec3b>(
+                (y + dy).clamp(0, frame.rows() - 1),
+                (x + dx).clamp(0, frame.cols() - 1),
+            )?;
+            sum += calibration::luma(&[bgr[2], bgr[1], bgr[0]]);
+        }
+    }
+
+    Ok(sum / 9.0)
+}
+
+pub fn read_tag(frame: &Mat, capture: bool) -> anyhow::Result<Option<Tag>> {
+    let Some(markers) = locate(frame, capture)? else {
+        return Ok(None);
+    };
+
+    let unit = Vector::from_slice(&[
+        Point2f::new(0.0, 0.0),
+        Point2f::new(1.0, 0.0),
+        Point2f::new(0.0, 1.0),
+        Point2f::new(1.0, 1.0),
+    ]);
+    let transform = imgproc::get_perspective_transform_def(&unit, &Vector::from_slice(&markers))?;
+
+    let mut cells = Vector::<Point2f>::new();
+    for u in [0.0, 1.0] {
+        for cell in 0..TAG_CELLS {
+            let (start, end) = tag_span(cell);
+            cells.push(Point2f::new(u, (start + end) / 2.0));
+        }
+    }
+
+    let mut points = Vector::<Point2f>::new();
+    core::perspective_transform(&cells, &mut points, &transform)?;
+
+    let mut samples = Vec::new();
+    for point in points {
+        samples.push(sample(frame, point)?);
+    }
+
+    let mut black = 0.0;
+    for marker in &markers {
+        black += sample(frame, *marker)? / markers.len() as f64;
+    }
+
+    let white = samples.iter().copied().fold(f64::MIN, f64::max);
+    let threshold = (black + white) / 2.0;
+
+    let code: Vec<bool> = samples.iter().map(|s| *s < threshold).collect();
+
+    Ok(Tag::from_bits(&code))
+}
+
+fn smooth(frame: &Mat, width: i32) -> anyhow::Result<Mat> {
+    let sigma = (frame.cols() as f64 / width as f64).max(1.0) * 0.6;
+
+    let mut smoothed = Mat::default();
+    imgproc::gaussian_blur_def(frame, &mut smoothed, Size::new(0, 0), sigma)?;
+    Ok(smoothed)
+}
+
+fn flatten(canvas: &Mat) -> anyhow::Result<Mat> {
+    let mut background = Mat::default();
+    imgproc::gaussian_blur_def(
+        canvas,
+        &mut background,
+        Size::new(0, 0),
+        canvas.rows() as f64 / 12.0,
+    )?;
+
+    let mut flat = Mat::default();
+    core::divide2(canvas, &background, &mut flat, 128.0, -1)?;
+    Ok(flat)
+}
+
+pub fn register(
+    frame: &Mat,
+    width: i32,
+    height: i32,
+    region: Rect,
+    capture: bool,
+) -> anyhow::Result<Mat> {
+    let smoothed;
+    let frame = if capture {
+        smoothed = smooth(frame, width)?;
+        &smoothed
+    } else {
+        frame
+    };
+
+    let mut canvas = Mat::default();
+
+    match locate(frame, capture)? {
+        Some(markers) => {
+            let source = Vector::from_slice(&markers);
+            let target = Vector::from_slice(&marker_centers(width, height));
+
+            let transform = imgproc::get_perspective_transform_def(&source, &target)?;
+            imgproc::warp_perspective_def(
+                frame,
+                &mut canvas,
+                &transform,
+                Size::new(width, height),
+            )?;
+        }
+        None => {
+            imgproc::resize(
+                frame,
+                &mut canvas,
+                Size::new(width, height),
+                0.0,
+                0.0,
+                imgproc::INTER_AREA,
+            )?;
+        }
+    }
+
+    if capture {
+        canvas = flatten(&canvas)?;
+    }
+
+    let area = Mat::roi(&canvas, region)?;
+    Ok(area.try_clone()?)
+}
+
+pub fn register_header(frame: &Mat, capture: bool) -> anyhow::Result<Mat> {
+    let region = header_area();
+    register(frame, HEADER_WIDTH, HEADER_HEIGHT, region, capture)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_round_trips_with_correctable_errors() {
+        for (kind, index) in [
+            (FrameKind::Header, 0),
+            (FrameKind::Calibration, 0),
+            (FrameKind::Palette, 0),
+            (FrameKind::Data, 1),
+            (FrameKind::Data, MAX_TAG_INDEX),
+        ] {
+            let mut code = Tag::new(kind, index).bits();
+            code[3] = !code[3];
+            code[30] = !code[30];
+
+            let tag = Tag::from_bits(&code).unwrap();
+            assert_eq!((tag.kind, tag.index), (kind, index));
+        }
+    }
+
+    #[test]
+    fn tag_rejects_unknown_kind_and_bad_check() {
+        let encode = |value: u32| {
+            let bits: Vec<bool> = (0..TAG_BITS).rev().map(|i| (value >> i) & 1 == 1).collect();
+            golay::encode(&bits, 1)
+        };
+
+        let index = 1234;
+        let check = Tag::check(5, index);
+        assert!(Tag::from_bits(&encode((5 << 21) | (check << 18) | index)).is_none());
+
+        let check = Tag::check(3, index) ^ 1;
+        assert!(Tag::from_bits(&encode((3 << 21) | (check << 18) | index)).is_none());
+    }
+
+    #[test]
+    fn tag_rejects_noise() {
+        let mut rng = crate::prng::Rng::new(7);
+        let accepted = (0..10_000)
+            .filter(|_| {
+                let code: Vec<bool> = (0..TAG_CELLS * 2)
+                    .map(|_| rng.next_u64() & 1 == 1)
+                    .collect();
+                Tag::from_bits(&code).is_some()
+            })
+            .count();
+
+        assert!(accepted < 100, "{} random tags accepted", accepted);
+    }
+
+    #[test]
+    fn register_recovers_captured_frame() {
+        use crate::simulate::{self, Degradation};
+
+        let (width, height, block) = (1280, 720, 16);
+        let region = data_area(width, height);
+        let (columns, rows) = (region.width / block, region.height / block);
+
+        let mut rng = crate::prng::Rng::new(11);
+        let bits: Vec<bool> = (0..rows * columns)
+            .map(|_| rng.next_u64() & 1 == 1)
+            .collect();
+
+        let mut data = Mat::new_rows_cols_with_default(
+            region.height,
+            region.width,
+            CV_8UC3,
+            Scalar::all(255.0),
+        )
+        .unwrap();
+        for (i, _) in bits.iter().enumerate().filter(|(_, bit)| **bit) {
+            let (column, row) = (i as i32 % columns, i as i32 / columns);
+            let cell = Rect::new(column * block, row * block, block, block);
+            imgproc::rectangle_def(&mut data, cell, Scalar::all(0.0)).unwrap();
+        }
+
+        let tag = Tag::new(FrameKind::Data, 4242);
+        let frame = compose(&data, region, None, width, height, tag).unwrap();
+
+        let degradations = [
+            Degradation::Perspective(0.04),
+            Degradation::Scale(0.75),
+            Degradation::Crop(3),
+        ];
+        let captured = simulate::degrade(frame, &degradations, &mut rng)
+            .unwrap()
+            .remove(0);
+
+        let tag = read_tag(&captured, true).unwrap().unwrap();
+        assert_eq!((tag.kind, tag.index), (FrameKind::Data, 4242));
+
+        let area = register(&captured, width, height, region, true).unwrap();
+        assert_eq!(area.size().unwrap(), region.size());
+
+        let samples: Vec<f64> = (0..bits.len() as i32)
+            .map(|i| {
+                let center = |n: i32| (n * block + block / 2) as f32;
+                sample(
+                    &area,
+                    Point2f::new(center(i % columns), center(i / columns)),
+                )
+                .unwrap()
+            })
+            .collect();
+        let threshold = samples.iter().sum::<f64>() / samples.len() as f64;
+
+        let errors = bits
+            .iter()
+            .zip(&samples)
+            .filter(|(bit, sample)| (**sample < threshold) != **bit)
+            .count();
+        assert!(
+            errors * 100 < bits.len(),
+            "{} of {} blocks",
+            errors,
+            bits.len()
+        );
+    }
+}
@@ -1,14 +1,22 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum OutputMode {
     Color,
     Binary,
+    Quantized,
+    Grayscale,
+    YCbCr,
+    Dct,
+    Ldpc,
+    Qr,
 }
 
 pub struct Data {
     pub bytes: Vec<u8>,
     pub binary: Vec<bool>,
     pub out_mode: OutputMode,
+    pub length: usize,
 }
 
 impl Data {
@@ -17,19 +25,76 @@ impl Data {
             bytes: Vec::new(),
             binary: Vec::new(),
             out_mode,
+            length: 0,
         }
     }
 
     pub fn from_binary(binary: Vec<bool>) -> Self {
         Data {
             bytes: Vec::new(),
+            length: binary.len() / 8,
             binary,
             out_mode: OutputMode::Binary,
         }
     }
 
+    pub fn from_quantized(binary: Vec<bool>) -> Self {
+        Data {
+            bytes: Vec::new(),
+            length: binary.len() / 8,
+            binary,
+            out_mode: OutputMode::Quantized,
+        }
+    }
+
+    pub fn from_grayscale(binary: Vec<bool>) -> Self {
+        Data {
+            bytes: Vec::new(),
+            length: binary.len() / 8,
+            binary,
+            out_mode: OutputMode::Grayscale,
+        }
+    }
+
+    pub fn from_ycbcr(binary: Vec<bool>) -> Self {
+        Data {
+            bytes: Vec::new(),
+            length: binary.len() / 8,
+            binary,
+            out_mode: OutputMode::YCbCr,
+        }
+    }
+
+    pub fn from_dct(binary: Vec<bool>) -> Self {
+        Data {
+            bytes: Vec::new(),
+            length: binary.len() / 8,
+            binary,
+            out_mode: OutputMode::Dct,
+        }
+    }
+
+    pub fn from_ldpc(binary: Vec<bool>, length: usize) -> Self {
+        Data {
+            bytes: Vec::new(),
+            binary,
+            out_mode: OutputMode::Ldpc,
+            length,
+        }
+    }
+
+    pub fn from_qr(bytes: Vec<u8>) -> Self {
+        Data {
+            length: bytes.len(),
+            bytes,
+            binary: Vec::new(),
+            out_mode: OutputMode::Qr,
+        }
+    }
+
     pub fn from_color(bytes: Vec<u8>) -> Self {
         Data {
+            length: bytes.len(),
             bytes,
             binary: Vec::new(),
             out_mode: OutputMode::Color,
@@ -37,7 +102,8 @@ impl Data {
     }
 }
 
-#[derive(Deserialize, Default)]
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
 pub struct Settings {
     pub size: i32,
 
@@ -48,6 +114,22 @@ pub struct Settings {
     pub fps: f64,
 
     pub height: i32,
+
+    pub bits: u8,
+
+    pub fec: u8,
+
+    pub header_interval: u32,
+
+    pub interleave_depth: u32,
+
+    pub seed: u32,
+
+    pub region: [i32; 4],
+
+    pub overlay_image: Option<String>,
+
+    pub overlay_text: Option<String>,
 }
 
 impl Settings {
@@ -58,6 +140,14 @@ impl Settings {
             fps: fps as f64,
             width,
             height,
+            bits: 0,
+            fec: 0,
+            header_interval: 0,
+            interleave_depth: 0,
+            seed: 0,
+            region: [0; 4],
+            overlay_image: None,
+            overlay_text: None,
         }
     }
 }
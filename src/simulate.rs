@@ -0,0 +1,355 @@
+use anyhow::anyhow;
+
+use opencv::core::{self, Mat, Point2f, Rect, Scalar, Size, Vector};
+use opencv::prelude::*;
+use opencv::{imgcodecs, imgproc};
+
+use crate::prng::Rng;
+
+pub enum Degradation {
+    Jpeg(i32),
+    Blur(f64),
+    Noise(f64),
+    Scale(f64),
+    Drop(f64),
+    Duplicate(f64),
+    Chroma,
+    Crop(i32),
+    Letterbox(i32),
+    Resize(f64),
+    Perspective(f64),
+    Gradient(f64),
+}
+
+pub fn parse_profile(profile: &str) -> anyhow::Result<Vec<Degradation>> {
+    let mut degradations = Vec::new();
+
+    for step in profile
+        .split([',', '+'])
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        let (name, value) = match step.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (step, None),
+        };
+
+        let number = || -> anyhow::Result<f64> {
+            value
+                .ok_or_else(|| anyhow!("Degradation '{}' needs a value", name))?
+                .parse::<f64>()
+                .map_err(|_| anyhow!("Invalid value for degradation '{}'", name))
+        };
+
+        let degradation = match name {
+            "jpeg" => Degradation::Jpeg(number()?.clamp(1.0, 100.0) as i32),
+            "blur" => Degradation::Blur(number()?),
+            "noise" => Degradation::Noise(number()?),
+            "scale" => Degradation::Scale(number()?.clamp(0.05, 1.0)),
+            "drop" => Degradation::Drop(number()?),
+            "duplicate" => Degradation::Duplicate(number()?),
+            "chroma" => Degradation::Chroma,
+            "crop" => Degradation::Crop(number()?.max(0.0) as i32),
+            "letterbox" => Degradation::Letterbox(number()?.max(0.0) as i32),
+            "resize" => Degradation::Resize(number()?.clamp(0.05, 4.0)),
+            "perspective" => Degradation::Perspective(number()?.clamp(0.0, 0.3)),
+            "gradient" => Degradation::Gradient(number()?.clamp(0.0, 1.0)),
+            _ => return Err(anyhow!("Unknown degradation '{}'", name)),
+        };
+
+        degradations.push(degradation);
+    }
+
+    Ok(degradations)
+}
+
+fn jpeg(frame: &Mat, quality: i32) -> anyhow::Result<Mat> {
+    let params = Vector::from_slice(&[imgcodecs::IMWRITE_JPEG_QUALITY, quality]);
+    let mut buffer = Vector::<u8>::new();
+
+    imgcodecs::imencode(".jpg", frame, &mut buffer, &params)?;
+    Ok(imgcodecs::imdecode(&buffer, imgcodecs::IMREAD_COLOR)?)
+}
+
+fn blur(frame: &Mat, sigma: f64) -> anyhow::Result<Mat> {
+    let mut blurred = Mat::default();
+    imgproc::gaussian_blur_def(frame, &mut blurred, Size::new(0, 0), sigma)?;
+    Ok(blurred)
+}
+
+fn noise(frame: &Mat, sigma: f64, rng: &mut Rng) -> anyhow::Result<Mat> {
+    let mut noisy = frame.try_clone()?;
+
+    for value in noisy.data_bytes_mut()? {
+        let noisy_value = *value as f64 + rng.gaussian() * sigma;
+        *value = noisy_value.round().clamp(0.0, 255.0) as u8;
+    }
+
+    Ok(noisy)
+}
+
+fn scale(frame: &Mat, factor: f64) -> anyhow::Result<Mat> {
+    let size = frame.size()?;
+    let small_size = Size::new(
+        ((size.width as f64 * factor).round() as i32).max(1),
+        ((size.height as f64 * factor).round() as i32).max(1),
+    );
+
+    let mut small = Mat::default();
+    imgproc::resize(frame, &mut small, small_size, 0.0, 0.0, imgproc::INTER_AREA)?;
+
+    let mut restored = Mat::default();
+    imgproc::resize(&small, &mut restored, size, 0.0, 0.0, imgproc::INTER_LINEAR)?;
+
+    Ok(restored)
+}
+
+fn chroma(frame: &Mat) -> anyhow::Result<Mat> {
+    let mut ycrcb = Mat::default();
+    imgproc::cvt_color_def(frame, &mut ycrcb, imgproc::COLOR_BGR2YCrCb)?;
+
+    let width = ycrcb.cols() as usize;
+    let height = ycrcb.rows() as usize;
+    let data = ycrcb.data_bytes_mut()?;
+
+    for y in (0..height).step_by(2) {
+        for x in (0..width).step_by(2) {
+            for channel in 1..3 {
+                let mut pixels = Vec::new();
+
+                for (dy, dx) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+                    if y + dy < height && x + dx < width {
+                        pixels.push(((y + dy) * width + x + dx) * 3 + channel);
+                    }
+                }
+
+                let average =
+                    pixels.iter().map(|&i| data[i] as usize).sum::<usize>() / pixels.len();
+
+                for i in pixels {
+                    data[i] = average as u8;
+                }
+            }
+        }
+    }
+
+    let mut subsampled = Mat::default();
+    imgproc::cvt_color_def(&ycrcb, &mut subsampled, imgproc::COLOR_YCrCb2BGR)?;
+    Ok(subsampled)
+}
+
+fn crop(frame: &Mat, pixels: i32) -> anyhow::Result<Mat> {
+    let size = frame.size()?;
+    let pixels = pixels.min((size.width - 1) / 2).min((size.height - 1) / 2);
+
+    let rect = Rect::new(
+        pixels,
+        pixels,
+        size.width - 2 * pixels,
+        size.height - 2 * pixels,
+    );
+
+    Ok(Mat::roi(frame, rect)?.try_clone()?)
+}
+
+fn letterbox(frame: &Mat, pixels: i32) -> anyhow::Result<Mat> {
+    let mut boxed = Mat::default();
+    core::copy_make_border(
+        frame,
+        &mut boxed,
+        pixels,
+        pixels,
+        0,
+        0,
+        core::BORDER_CONSTANT,
+        Scalar::all(0.0),
+    )?;
+    Ok(boxed)
+}
+
+fn resize(frame: &Mat, factor: f64) -> anyhow::Result<Mat> {
+    let mut resized = Mat::default();
+    imgproc::resize(
+        frame,
+        &mut resized,
+        Size::new(0, 0),
+        factor,
+        factor,
+        imgproc::INTER_AREA,
+    )?;
+    Ok(resized)
+}
+
+fn perspective(frame: &Mat, amount: f64, rng: &mut Rng) -> anyhow::Result<Mat> {
+    let size = frame.size()?;
+    let width = size.width as f32;
+    let height = size.height as f32;
+
+    let corners = [(0.0, 0.0), (width, 0.0), (0.0, height), (width, height)];
+    let mut source = Vector::<Point2f>::new();
+    let mut target = Vector::<Point2f>::new();
+
+    for (x, y) in corners {
+        let inset_x = (rng.next_f64() * amount) as f32 * width;
+        let inset_y = (rng.next_f64() * amount) as f32 * height;
+
+        source.push(Point2f::new(x, y));
+        target.push(Point2f::new(
+            if x == 0.0 { inset_x } else { x - inset_x },
+            if y == 0.0 { inset_y } else { y - inset_y },
+        ));
+    }
+
+    let transform = imgproc::get_perspective_transform_def(&source, &target)?;
+
+    let mut warped = Mat::default();
+    imgproc::warp_perspective(
+        frame,
+        &mut warped,
+        &transform,
+        size,
+        imgproc::INTER_LINEAR,
+        core::BORDER_CONSTANT,
+        Scalar::all(40.0),
+    )?;
+    Ok(warped)
+}
+
+fn gradient(frame: &Mat, strength: f64) -> anyhow::Result<Mat> {
+    let mut shaded = frame.try_clone()?;
+    let width = shaded.cols() as usize;
+    let height = shaded.rows() as usize;
+    let data = shaded.data_bytes_mut()?;
+
+    for y in 0..height {
+        for x in 0..width {
+            let falloff = (x + y) as f64 / (width + height) as f64;
+            let gain = 1.0 - strength * falloff;
+
+            for channel in 0..3 {
+                let i = (y * width + x) * 3 + channel;
+                data[i] = (data[i] as f64 * gain).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+
+    Ok(shaded)
+}
+
+pub fn degrade(
+    mut frame: Mat,
+    degradations: &[Degradation],
+    rng: &mut Rng,
+) -> anyhow::Result<Vec<Mat>> {
+    let mut copies = 1;
+
+    for degradation in degradations {
+        match degradation {
+            Degradation::Jpeg(quality) => frame = jpeg(&frame, *quality)?,
+            Degradation::Blur(sigma) => frame = blur(&frame, *sigma)?,
+            Degradation::Noise(sigma) => frame = noise(&frame, *sigma, rng)?,
+            Degradation::Scale(factor) => frame = scale(&frame, *factor)?,
+            Degradation::Chroma => frame = chroma(&frame)?,
+            Degradation::Crop(pixels) => frame = crop(&frame, *pixels)?,
+            Degradation::Letterbox(pixels) => frame = letterbox(&frame, *pixels)?,
+            Degradation::Resize(factor) => frame = resize(&frame, *factor)?,
+            Degradation::Perspective(amount) => frame = perspective(&frame, *amount, rng)?,
+            Degradation::Gradient(strength) => frame = gradient(&frame, *strength)?,
+            Degradation::Drop(probability) => {
+                if rng.next_f64() < *probability {
+                    copies = 0;
+                }
+            }
+            Degradation::Duplicate(probability) => {
+                if copies > 0 && rng.next_f64() < *probability {
+                    copies = 2;
+                }
+            }
+        }
+    }
+
+    let mut frames = Vec::new();
+    for _ in 0..copies {
+        frames.push(frame.try_clone()?);
+    }
+
+    Ok(frames)
+}
+
+pub fn bit_errors(reference: &[u8], received: &[u8]) -> (usize, usize) {
+    let total = reference.len() * 8;
+
+    let mut errors: usize = reference
+        .iter()
+        .zip(received)
+        .map(|(a, b)| (a ^ b).count_ones() as usize)
+        .sum();
+
+    if received.len() < reference.len() {
+        errors += (reference.len() - received.len()) * 8;
+    }
+
+    (errors, total)
+}
+
+pub struct Report {
+    pub errors: usize,
+
+    pub total: usize,
+
+    pub recovered: bool,
+}
+
+impl Report {
+    pub fn bit_error_rate(&self) -> f64 {
+        if self.total == 0 {
+            return 1.0;
+        }
+
+        self.errors as f64 / self.total as f64
+    }
+}
+
+pub fn evaluate(
+    frames: impl Iterator<Item = Mat>,
+    reference: &[u8],
+    degradations: &[Degradation],
+    seed: u64,
+    capture: bool,
+) -> Report {
+    let mut rng = Rng::new(seed);
+
+    let degraded: Vec<Mat> = frames
+        .flat_map(|frame| match degrade(frame, degradations, &mut rng) {
+            Ok(frames) => frames,
+            Err(e) => {
+                println!("Failed to degrade frame: {}", e);
+                Vec::new()
+            }
+        })
+        .collect();
+
+    let open = || -> anyhow::Result<_> { Ok(degraded.iter().cloned()) };
+
+    match crate::ethcer::read_frames(open, 1, capture) {
+        Ok(received) => {
+            let (errors, total) = bit_errors(reference, &received);
+
+            Report {
+                errors,
+                total,
+                recovered: received.len() >= reference.len()
+                    && received[..reference.len()] == *reference,
+            }
+        }
+        Err(e) => {
+            println!("Decoding failed: {}", e);
+
+            Report {
+                errors: reference.len() * 8,
+                total: reference.len() * 8,
+                recovered: false,
+            }
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use anyhow::anyhow;
+
+use opencv::core::{Mat, Size};
+use opencv::prelude::*;
+use opencv::videoio::{CAP_ANY, VideoCapture, VideoWriter};
+
+use crate::calibration;
+use crate::dct;
+use crate::ethcer;
+use crate::fec;
+use crate::hash;
+use crate::prng::Rng;
+use crate::timer::Timer;
+use crate::whiten;
+
+const MAGIC: &[u8; 4] = b"VVSG";
+
+const HEADER_BYTES: usize = 8;
+
+const COPIES: u8 = 5;
+
+const STEP: f64 = 32.0;
+
+const COEFFICIENT: (usize, usize) = (2, 1);
+
+pub enum Carrier {
+    // Least significant bit of every channel, needs lossless output
+    Lsb,
+
+    // Quantised mid-frequency luma DCT coefficient, survives lossy output
+    Dct,
+}
+
+impl Carrier {
+    fn copies(&self) -> u8 {
+        match self {
+            Carrier::Lsb => 1,
+            Carrier::Dct => COPIES,
+        }
+    }
+
+    fn slots(&self, width: usize, height: usize) -> usize {
+        match self {
+            Carrier::Lsb => width * height * 3,
+            Carrier::Dct => (width / dct::TILE as usize) * (height / dct::TILE as usize),
+        }
+    }
+}
+
+fn key_seed(key: &str) -> u64 {
+    hash::fnv1a_64(key.as_bytes())
+}
+
+fn order(slots: usize, seed: u64, frame: usize) -> Vec<usize> {
+    let mut rng = Rng::new(seed ^ (frame as u64).wrapping_mul(0xD6E8_FEB8_6659_FD93));
+    let mut order: Vec<usize> = (0..slots).collect();
+
+    for i in (1..slots).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        order.swap(i, j);
+    }
+
+    order
+}
+
+fn dither(seed: u64, frame: usize, block: usize) -> f64 {
+    let mut rng = Rng::new(seed ^ ((frame as u64) << 32 | block as u64));
+    rng.next_f64() * STEP
+}
+
+fn lattice(coefficient: f64, offset: f64) -> f64 {
+    ((coefficient - offset) / STEP).round() * STEP + offset
+}
+
+fn offset(bit: bool, dither: f64) -> f64 {
+    if bit { dither + STEP / 2.0 } else { dither }
+}
+
+fn block_pixels(
+    width: usize,
+    blocks_x: usize,
+    block: usize,
+) -> impl Iterator<Item = (usize, usize, usize)> {
+    let tile = dct::TILE as usize;
+    let left = (block % blocks_x) * tile;
+    let top = (block / blocks_x) * tile;
+
+    (0..tile).flat_map(move |y| (0..tile).map(move |x| (x, y, ((top + y) * width + left + x) * 3)))
+}
+
+fn coefficient(data: &[u8], width: usize, blocks_x: usize, block: usize) -> f64 {
+    let (u, v) = COEFFICIENT;
+
+    block_pixels(width, blocks_x, block)
+        .map(|(x, y, i)| {
+            calibration::luma(&[data[i + 2], data[i + 1], data[i]]) * dct::basis(u, v, x, y)
+        })
+        .sum()
+}
+
+fn write_bits(
+    frame: &mut Mat,
+    bits: &[bool],
+    carrier: &Carrier,
+    seed: u64,
+    index: usize,
+) -> anyhow::Result<()> {
+    let width = frame.cols() as usize;
+    let blocks_x = width / dct::TILE as usize;
+    let order = order(carrier.slots(width, frame.rows() as usize), seed, index);
+    let data = frame.data_bytes_mut()?;
+
+    for (bit, &slot) in bits.iter().zip(&order) {
+        match carrier {
+            Carrier::Lsb => data[slot] = (data[slot] & !1) | *bit as u8,
+            Carrier::Dct => {
+                let current = coefficient(data, width, blocks_x, slot);
+                let target = lattice(current, offset(*bit, dither(seed, index, slot)));
+                let delta = target - current;
+                let (u, v) = COEFFICIENT;
+
+                for (x, y, i) in block_pixels(width, blocks_x, slot) {
+                    let change = delta * dct::basis(u, v, x, y);
+
+                    for channel in &mut data[i..i + 3] {
+                        *channel = (*channel as f64 + change).round().clamp(0.0, 255.0) as u8;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn read_bits(frame: &Mat, carrier: &Carrier, seed: u64, index: usize) -> anyhow::Result<Vec<bool>> {
+    let width = frame.cols() as usize;
+    let blocks_x = width / dct::TILE as usize;
+    let order = order(carrier.slots(width, frame.rows() as usize), seed, index);
+    let data = frame.data_bytes()?;
+
+    Ok(order
+        .iter()
+        .map(|&slot| match carrier {
+            Carrier::Lsb => data[slot] & 1 == 1,
+            Carrier::Dct => {
+                let current = coefficient(data, width, blocks_x, slot);
+                let dither = dither(seed, index, slot);
+                let zero = (current - lattice(current, offset(false, dither))).abs();
+                let one = (current - lattice(current, offset(true, dither))).abs();
+
+                one < zero
+            }
+        })
+        .collect())
+}
+
+pub fn capacity(frames: usize, width: usize, height: usize, carrier: &Carrier) -> usize {
+    let bits = frames * carrier.slots(width, height);
+    (bits / 8 / carrier.copies() as usize).saturating_sub(HEADER_BYTES)
+}
+
+#[derive(Default)]
+pub struct Psnr {
+    squared: f64,
+
+    count: usize,
+}
+
+impl Psnr {
+    pub fn add(&mut self, cover: &Mat, stego: &Mat) -> anyhow::Result<()> {
+        for (x, y) in cover.data_bytes()?.iter().zip(stego.data_bytes()?) {
+            let difference = *x as f64 - *y as f64;
+            self.squared += difference * difference;
+            self.count += 1;
+        }
+
+        Ok(())
+    }
+
+    pub fn value(&self) -> f64 {
+        if self.squared == 0.0 {
+            return f64::INFINITY;
+        }
+
+        10.0 * (255.0 * 255.0 / (self.squared / self.count as f64)).log10()
+    }
+}
+
+fn writer(path: &str, frame: &Mat, fps: f64, carrier: &Carrier) -> anyhow::Result<VideoWriter> {
+    let fourcc = match carrier {
+        Carrier::Lsb => VideoWriter::fourcc('p', 'n', 'g', ' ')?,
+        Carrier::Dct => VideoWriter::fourcc('a', 'v', 'c', '1')?,
+    };
+
+    let video = VideoWriter::new(
+        path,
+        fourcc,
+        fps,
+        Size::new(frame.cols(), frame.rows()),
+        true,
+    )?;
+
+    if !video.is_opened()? {
+        return Err(anyhow!("Could not open {} for writing", path));
+    }
+
+    Ok(video)
+}
+
+pub fn hide(
+    cover: impl Iterator<Item = Mat>,
+    payload: &[u8],
+    key: &str,
+    carrier: &Carrier,
+    path: &str,
+    fps: f64,
+) -> anyhow::Result<f64> {
+    let _timer = Timer::new("Hiding data");
+    let seed = key_seed(key);
+
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(payload);
+
+    let bits = ethcer::rip_bits(&fec::encode(
+        &whiten::scramble(&bytes, seed),
+        carrier.copies(),
+    ));
+    let mut remaining = &bits[..];
+
+    let mut cover = cover.peekable();
+    let first = cover
+        .peek()
+        .ok_or_else(|| anyhow!("Cover video has no frames"))?;
+    let slots = carrier.slots(first.cols() as usize, first.rows() as usize);
+
+    if slots < HEADER_BYTES * 8 * carrier.copies() as usize {
+        return Err(anyhow!("Cover frames are too small to hold the header"));
+    }
+
+    let mut video = writer(path, first, fps, carrier)?;
+    let mut psnr = Psnr::default();
+    let mut written = 0;
+
+    for (index, frame) in cover.enumerate() {
+        let mut stego = frame.try_clone()?;
+
+        if !remaining.is_empty() {
+            let count = slots.min(remaining.len());
+            write_bits(&mut stego, &remaining[..count], carrier, seed, index)?;
+            remaining = &remaining[count..];
+        }
+
+        psnr.add(&frame, &stego)?;
+        video.write(&stego)?;
+        written += 1;
+    }
+    video.release()?;
+
+    if !remaining.is_empty() {
+        return Err(anyhow!(
+            "Cover video ended before the payload was hidden, {} of {} bits written",
+            bits.len() - remaining.len(),
+            bits.len()
+        ));
+    }
+
+    println!("{} frames written to {}", written, path);
+    Ok(psnr.value())
+}
+
+fn header(bits: &[bool], carrier: &Carrier, seed: u64) -> Option<usize> {
+    let coded = HEADER_BYTES * 8 * carrier.copies() as usize;
+    let bytes = fec::decode(
+        &ethcer::translate_u8(bits.get(..coded)?.to_vec()).ok()?,
+        carrier.copies(),
+    );
+    let header = whiten::scramble(&bytes, seed);
+
+    if &header[..4] != MAGIC {
+        return None;
+    }
+
+    Some(u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize)
+}
+
+pub fn extract(mut frames: impl Iterator<Item = Mat>, key: &str) -> anyhow::Result<Vec<u8>> {
+    let _timer = Timer::new("Extracting hidden data");
+    let seed = key_seed(key);
+    let first = frames
+        .next()
+        .ok_or_else(|| anyhow!("Video has no frames"))?;
+
+    let (carrier, length, mut bits) = [Carrier::Lsb, Carrier::Dct]
+        .into_iter()
+        .find_map(|carrier| {
+            let bits = read_bits(&first, &carrier, seed, 0).ok()?;
+            let length = header(&bits, &carrier, seed)?;
+            Some((carrier, length, bits))
+        })
+        .ok_or_else(|| anyhow!("No hidden payload found, check the key"))?;
+
+    let needed = (HEADER_BYTES + length) * 8 * carrier.copies() as usize;
+
+    for (index, frame) in frames.enumerate() {
+        if bits.len() >= needed {
+            break;
+        }
+
+        bits.extend(read_bits(&frame, &carrier, seed, index + 1)?);
+    }
+
+    if bits.len() < needed {
+        return Err(anyhow!(
+            "Video ended before the hidden payload was complete"
+        ));
+    }
+
+    let bytes = fec::decode(
+        &ethcer::translate_u8(bits[..needed].to_vec())?,
+        carrier.copies(),
+    );
+    Ok(whiten::scramble(&bytes, seed)[HEADER_BYTES..].to_vec())
+}
+
+pub fn verify(path: &str, cover_path: &str, payload: &[u8], key: &str) -> anyhow::Result<f64> {
+    let open = |path: &str| -> anyhow::Result<_> {
+        let video = VideoCapture::from_file(path, CAP_ANY)?;
+
+        if !video.is_opened()? {
+            return Err(anyhow!("Could not open {}", path));
+        }
+
+        Ok(ethcer::video_frames(video))
+    };
+
+    let mut psnr = Psnr::default();
+    for (stego, cover) in open(path)?.zip(open(cover_path)?) {
+        psnr.add(&cover, &stego)?;
+    }
+
+    if extract(open(path)?, key)? != payload {
+        return Err(anyhow!(
+            "{} does not give back the hidden payload, try a smaller payload or a longer cover",
+            path
+        ));
+    }
+
+    Ok(psnr.value())
+}
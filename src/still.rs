@@ -0,0 +1,130 @@
+use std::fs;
+
+use anyhow::anyhow;
+
+use opencv::core::{self, Mat, Rect, Vector};
+use opencv::imgcodecs;
+use opencv::prelude::*;
+
+use crate::ethcer;
+use crate::ldpc;
+use crate::png;
+use crate::settings::{OutputMode, Settings};
+
+const MIN_WIDTH: i32 = 256;
+
+const QR_MIN_WIDTH: i32 = 1280;
+
+const MAX_WIDTH: i32 = 3840;
+
+pub const IMAGE_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "bmp", "tif", "tiff"];
+
+const FRAME_HEIGHT_KEY: &str = "vidvault-frame-height";
+
+fn tile_height(width: i32) -> i32 {
+    (width as f64 * 9.0 / 16.0).round() as i32
+}
+
+fn coded_bits(out_mode: &OutputMode, length: usize, fec: u8) -> usize {
+    let bits = length * fec as usize * 8;
+
+    match out_mode {
+        OutputMode::Ldpc => bits.div_ceil(ldpc::DATA_BITS) * ldpc::CODE_BITS,
+        _ => bits,
+    }
+}
+
+pub fn fit(out_mode: &OutputMode, length: usize, settings: &Settings) -> (i32, i32) {
+    let needed = coded_bits(out_mode, length, settings.fec);
+
+    let mut probe = Settings::new(settings.size, settings.threads, 0, 0, 0);
+    probe.bits = settings.bits;
+    probe.region = settings.region;
+
+    let min_width = match out_mode {
+        OutputMode::Qr => QR_MIN_WIDTH,
+        _ => MIN_WIDTH,
+    };
+
+    for width in (min_width..=MAX_WIDTH).step_by(16) {
+        probe.width = width;
+        probe.height = tile_height(width);
+
+        if ethcer::bits_per_frame(out_mode, &probe) >= needed {
+            return (probe.width, probe.height);
+        }
+    }
+
+    (MAX_WIDTH, tile_height(MAX_WIDTH))
+}
+
+pub fn write(path: &str, frames: &[Mat]) -> anyhow::Result<()> {
+    let first = frames
+        .first()
+        .ok_or_else(|| anyhow!("No frames to write"))?;
+
+    let mut image = Mat::default();
+    core::vconcat(&Vector::from_iter(frames.iter().cloned()), &mut image)?;
+
+    let mut encoded = Vector::<u8>::new();
+    imgcodecs::imencode_def(".png", &image, &mut encoded)?;
+    let bytes = png::with_text(
+        encoded.as_slice(),
+        FRAME_HEIGHT_KEY,
+        &first.rows().to_string(),
+    )?;
+
+    fs::write(path, bytes)?;
+    println!(
+        "{} frames written to {} ({}x{})",
+        frames.len(),
+        path,
+        image.cols(),
+        image.rows()
+    );
+    Ok(())
+}
+
+pub fn read(path: &str) -> anyhow::Result<Vec<Mat>> {
+    let bytes = fs::read(path)?;
+    let image = imgcodecs::imdecode(&Vector::<u8>::from_slice(&bytes), imgcodecs::IMREAD_COLOR)?;
+
+    if image.empty() {
+        return Err(anyhow!("Failed to read image {}", path));
+    }
+
+    let width = image.cols();
+
+    // Re-saved or converted images lose the text chunk, those fall back to 16:9 frames
+    let height = match png::text(&bytes, FRAME_HEIGHT_KEY) {
+        Some(height) => height
+            .parse::<i32>()
+            .ok()
+            .filter(|height| *height > 0)
+            .ok_or_else(|| anyhow!("Invalid frame height '{}' in {}", height, path))?,
+        None => tile_height(width),
+    };
+
+    if image.rows() % height != 0 {
+        return Err(anyhow!(
+            "Image {} is {} pixels high, which is not a whole number of {} pixel frames",
+            path,
+            image.rows(),
+            height
+        ));
+    }
+
+    let mut frames = Vec::new();
+    for y in (0..image.rows()).step_by(height as usize) {
+        frames.push(Mat::roi(&image, Rect::new(0, y, width, height))?.try_clone()?);
+    }
+
+    Ok(frames)
+}
+
+pub fn is_image(path: &str) -> bool {
+    std::path::Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
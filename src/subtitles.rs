@@ -0,0 +1,214 @@
+use std::fs;
+use std::path::Path;
+
+use crate::base64;
+use crate::ethcer::{self, HEADER_WORDS};
+use crate::ffmpeg::{self, TempFile};
+use crate::hash;
+
+const HEADER_RECORD: u8 = 1;
+
+const MANIFEST_RECORD: u8 = 2;
+
+const CUE_SECONDS: f64 = 10.0;
+
+#[derive(Default)]
+pub struct Track {
+    pub header: Option<Vec<u32>>,
+
+    pub manifest: Option<String>,
+}
+
+fn record(kind: u8, payload: &[u8]) -> String {
+    let mut bytes = vec![kind];
+    bytes.extend_from_slice(payload);
+    bytes.extend_from_slice(&hash::fnv1a_32(&bytes).to_be_bytes());
+
+    base64::encode(&bytes)
+}
+
+fn parse_record(text: &str) -> Option<(u8, Vec<u8>)> {
+    let bytes = base64::decode(text).ok()?;
+
+    if bytes.len() < 5 {
+        return None;
+    }
+
+    let (body, sum) = bytes.split_at(bytes.len() - 4);
+    if hash::fnv1a_32(body).to_be_bytes() != sum {
+        return None;
+    }
+
+    Some((body[0], body[1..].to_vec()))
+}
+
+fn timestamp(seconds: f64) -> String {
+    let millis = (seconds * 1000.0).round() as u64;
+
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+pub fn to_srt(words: &[u32], manifest: &str, duration: f64) -> String {
+    let header_bytes: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+    let records = [
+        record(HEADER_RECORD, &header_bytes),
+        record(MANIFEST_RECORD, manifest.as_bytes()),
+    ];
+
+    let cues = ((duration / CUE_SECONDS).ceil() as usize).max(records.len());
+    let mut srt = String::new();
+
+    for cue in 0..cues {
+        let start = cue as f64 * CUE_SECONDS;
+
+        srt.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            cue + 1,
+            timestamp(start),
+            timestamp(start + CUE_SECONDS),
+            records[cue % records.len()]
+        ));
+    }
+
+    srt
+}
+
+pub fn parse_srt(text: &str) -> Track {
+    let mut track = Track::default();
+
+    for cue in text.replace('\r', "").split("\n\n") {
+        let payload: String = cue
+            .lines()
+            .skip_while(|line| !line.contains("-->"))
+            .skip(1)
+            .collect();
+
+        match parse_record(payload.trim()) {
+            Some((HEADER_RECORD, bytes)) if bytes.len() == HEADER_WORDS * 4 => {
+                track.header = Some(
+                    bytes
+                        .chunks(4)
+                        .map(|word| u32::from_be_bytes([word[0], word[1], word[2], word[3]]))
+                        .collect(),
+                );
+            }
+            Some((MANIFEST_RECORD, bytes)) => {
+                track.manifest = Some(String::from_utf8_lossy(&bytes).to_string());
+            }
+            _ => {}
+        }
+    }
+
+    track
+}
+
+pub fn mux(video_path: &str, words: &[u32], manifest: &str, fps: f64) -> anyhow::Result<String> {
+    let duration = ethcer::video_duration(video_path, fps)?;
+
+    let srt_path = Path::new(video_path).with_extension("srt");
+    let srt_path = srt_path.to_string_lossy();
+    fs::write(&*srt_path, to_srt(words, manifest, duration))?;
+
+    let out_path = Path::new(video_path).with_extension("mkv");
+    let out_path = out_path.to_string_lossy();
+
+    let muxed = ffmpeg::run(&[
+        "-i", video_path, "-i", &srt_path, "-map", "0", "-map", "1", "-c", "copy", "-c:s", "srt",
+        &out_path,
+    ]);
+
+    if !muxed {
+        println!(
+            "Warning: ffmpeg could not mux the subtitle track, it was left at {}",
+            srt_path
+        );
+        return Ok(video_path.to_string());
+    }
+
+    fs::remove_file(video_path)?;
+    fs::remove_file(&*srt_path)?;
+    println!("Subtitle track muxed into {}", out_path);
+    Ok(out_path.to_string())
+}
+
+pub fn read(video_path: &str) -> Track {
+    let sidecar = Path::new(video_path).with_extension("srt");
+
+    let text = match embedded(video_path) {
+        Some(text) => text,
+        None => fs::read_to_string(sidecar).unwrap_or_default(),
+    };
+
+    let track = parse_srt(&text);
+
+    if let Some(manifest) = &track.manifest {
+        println!("Manifest from the subtitle track:");
+        for line in manifest.lines() {
+            if let Some((key, value)) = line.split_once('=') {
+                println!("  {:<16} {}", key, value);
+            }
+        }
+    }
+
+    track
+}
+
+fn embedded(video_path: &str) -> Option<String> {
+    if !ffmpeg::has_stream(video_path, "subtitle") {
+        return None;
+    }
+
+    let srt_file = TempFile::new("srt").ok()?;
+    let srt_path = srt_file.path();
+
+    ffmpeg::run(&["-i", video_path, "-map", "0:s:0", "-f", "srt", &srt_path])
+        .then(|| fs::read_to_string(&srt_path).ok())
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> Vec<u32> {
+        (0..HEADER_WORDS as u32).map(|i| i * 1000 + 7).collect()
+    }
+
+    #[test]
+    fn srt_round_trips_header_and_manifest() {
+        let srt = to_srt(&header(), "format=vidvault/1\nname=a.txt", 35.0);
+        let track = parse_srt(&srt.replace('\n', "\r\n"));
+
+        assert!(srt.starts_with("1\n00:00:00,000 --> 00:00:10,000\n"));
+        assert_eq!(track.header, Some(header()));
+        assert_eq!(
+            track.manifest.as_deref(),
+            Some("format=vidvault/1\nname=a.txt")
+        );
+    }
+
+    #[test]
+    fn corrupted_cues_are_ignored() {
+        let srt = to_srt(&header(), "name=a.txt", 0.0);
+        let mut cues: Vec<String> = srt.split("\n\n").map(str::to_string).collect();
+        let payload = cues[0].lines().nth(2).unwrap().to_string();
+        let flipped = if payload.starts_with('A') { "B" } else { "A" };
+        cues[0] = cues[0].replace(&payload, &format!("{}{}", flipped, &payload[1..]));
+
+        let track = parse_srt(&cues.join("\n\n"));
+        assert_eq!(track.header, None);
+        assert_eq!(track.manifest.as_deref(), Some("name=a.txt"));
+    }
+
+    #[test]
+    fn timestamps_roll_over() {
+        assert_eq!(timestamp(0.0), "00:00:00,000");
+        assert_eq!(timestamp(3725.5), "01:02:05,500");
+    }
+}
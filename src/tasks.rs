@@ -1,8 +1,12 @@
 use crate::args::Arguments;
 
+pub mod autotune;
 pub mod dislodge;
 pub mod download;
 pub mod embed;
+pub mod hide;
+pub mod inspect;
+pub mod simulate;
 
 pub async fn run_by_args(args: Arguments) -> anyhow::Result<()> {
     match args.command.expect("Command was not provided by the user") {
@@ -11,5 +15,13 @@ pub async fn run_by_args(args: Arguments) -> anyhow::Result<()> {
         crate::args::Commands::Dislodge(args) => dislodge::run_dislodge(args).await,
 
         crate::args::Commands::Download(args) => download::run_download(args).await,
+
+        crate::args::Commands::Simulate(args) => simulate::run_simulate(args).await,
+
+        crate::args::Commands::Autotune(args) => autotune::run_autotune(args).await,
+
+        crate::args::Commands::Inspect(args) => inspect::run_inspect(args).await,
+
+        crate::args::Commands::Hide(args) => hide::run_hide(args).await,
     }
 }
@@ -0,0 +1,79 @@
+use crate::{
+    args::AutotuneParams,
+    autotune, ethcer,
+    presets::{self, SavedPreset},
+    prng::Rng,
+    simulate::{self, Degradation},
+    tasks::embed,
+};
+
+pub async fn run_autotune(args: AutotuneParams) -> anyhow::Result<()> {
+    let profile = args.profile.expect("Profile was not provided by the user");
+    let name = args.name.expect("Preset name was not provided by the user");
+
+    let degradations = simulate::parse_profile(&profile)?;
+
+    let mut stressed = simulate::parse_profile(&profile)?;
+    stressed.push(Degradation::Noise(args.margin.unwrap_or(8.0)));
+
+    let payload = match args.in_path {
+        Some(path) => ethcer::rip_bytes(&path)?,
+        None => {
+            let mut rng = Rng::new(1337);
+            (0..4096).map(|_| rng.next_u64() as u8).collect()
+        }
+    };
+
+    let (width, height) = embed::resolution(args.resolution.as_deref());
+
+    for candidate in autotune::candidates(width, height) {
+        println!("Trying {}", candidate.describe());
+
+        let report = match autotune::evaluate(&candidate, &payload, &degradations, 1337) {
+            Ok(report) => report,
+            Err(e) => {
+                println!("Skipping {}: {}", candidate.describe(), e);
+                continue;
+            }
+        };
+        if !report.recovered {
+            println!("Failed under '{}'", profile);
+            continue;
+        }
+
+        let margin_report = match autotune::evaluate(&candidate, &payload, &stressed, 7331) {
+            Ok(margin_report) => margin_report,
+            Err(e) => {
+                println!("Skipping {}: {}", candidate.describe(), e);
+                continue;
+            }
+        };
+        if !margin_report.recovered {
+            println!(
+                "Survived '{}' but failed the safety margin (bit error rate {:.6})",
+                profile,
+                margin_report.bit_error_rate()
+            );
+            continue;
+        }
+
+        println!(
+            "Found {} at {:.0} payload bits per frame",
+            candidate.describe(),
+            candidate.density()
+        );
+
+        return presets::save(
+            &name,
+            SavedPreset {
+                mode: candidate.mode,
+                settings: candidate.settings,
+            },
+        );
+    }
+
+    Err(anyhow::anyhow!(
+        "No settings survived '{}', try a milder profile",
+        profile
+    ))
+}
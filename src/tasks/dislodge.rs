@@ -1,12 +1,37 @@
-use crate::{args::DislodgeParams, ethcer};
+use std::path::Path;
+
+use opencv::videoio::{CAP_ANY, VideoCapture};
+
+use crate::{args::DislodgeParams, ethcer, gif, paper, stego, still, webp};
 
 pub async fn run_dislodge(args: DislodgeParams) -> anyhow::Result<()> {
-    let out_data = ethcer::read(
-        &args
-            .in_path
-            .expect("Input path was not provided by the user"),
-        1,
-    )?;
+    let in_path = args
+        .in_path
+        .expect("Input path was not provided by the user");
+
+    let extension = Path::new(&in_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_lowercase);
+
+    let out_data = if let Some(key) = &args.key {
+        let video = VideoCapture::from_file(&in_path, CAP_ANY)?;
+        stego::extract(ethcer::video_frames(video), key)?
+    } else if args.scan {
+        let pages = paper::read_scans(&in_path)?;
+        ethcer::read_frames(|| Ok(pages.iter().cloned()), 1, true)?
+    } else if extension.as_deref() == Some("gif") {
+        let frames = gif::read(&in_path)?;
+        ethcer::read_frames(|| Ok(frames.iter().cloned()), 1, args.capture)?
+    } else if extension.as_deref() == Some("webp") {
+        let frames = webp::read(&in_path)?;
+        ethcer::read_frames(|| Ok(frames.iter().cloned()), 1, args.capture)?
+    } else if still::is_image(&in_path) {
+        let frames = still::read(&in_path)?;
+        ethcer::read_frames(|| Ok(frames.iter().cloned()), 1, args.capture)?
+    } else {
+        ethcer::read(&in_path, 1, args.capture)?
+    };
 
     let out_path = args
         .out_path
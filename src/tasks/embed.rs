@@ -1,9 +1,40 @@
+use std::path::Path;
+
 use crate::{
-    args::{EmbedParams, EmbedPreset},
-    ethcer,
-    settings::{Data, OutputMode, Settings},
+    args::{EmbedParams, EmbedPreset, OutputFormat},
+    audio, ethcer, gif, paper, presets, registration,
+    settings::{OutputMode, Settings},
+    still, subtitles,
+    titlecard::{self, TitleCard},
+    webp,
 };
 
+pub fn resolution(name: Option<&str>) -> (i32, i32) {
+    match name {
+        Some("144p") => (256, 144),
+        Some("240p") => (426, 240),
+        Some("360p") => (640, 360),
+        Some("480p") => (854, 480),
+        Some("720p") => (1280, 720),
+        _ => (640, 360),
+    }
+}
+
+fn parse_region(region: &str) -> anyhow::Result<[i32; 4]> {
+    let values = region
+        .split(',')
+        .map(|value| value.trim().parse::<i32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| anyhow::anyhow!("Region must be given as X,Y,WIDTH,HEIGHT"))?;
+
+    match values[..] {
+        [x, y, width, height] if x >= 0 && y >= 0 && width > 0 && height > 0 => {
+            Ok([x, y, width, height])
+        }
+        _ => Err(anyhow::anyhow!("Region must be given as X,Y,WIDTH,HEIGHT")),
+    }
+}
+
 pub async fn run_embed(args: EmbedParams) -> anyhow::Result<()> {
     let mut settings = Settings::default();
     let mut out_mode = OutputMode::Binary;
@@ -33,25 +64,28 @@ pub async fn run_embed(args: EmbedParams) -> anyhow::Result<()> {
             settings.width = 1280;
             settings.height = 720;
         }
+        Some(EmbedPreset::Grayscale) => {
+            out_mode = OutputMode::Grayscale;
+            settings.size = 2;
+            settings.bits = 2;
+            settings.threads = 8;
+            settings.fps = 10.0;
+            settings.width = 1280;
+            settings.height = 720;
+        }
         None => {}
     }
 
+    if let Some(name) = &args.saved_preset {
+        let saved = presets::load(name)?;
+        out_mode = saved.mode;
+        settings = saved.settings;
+    }
+
     if settings.width == 0 || settings.height == 0 {
-        if args.resolution.is_none() {
-            settings.width = 640;
-            settings.height = 360;
-        } else {
-            let (width, height) = match args.resolution.unwrap().as_str() {
-                "144p" => (256, 144),
-                "240p" => (426, 240),
-                "360p" => (640, 360),
-                "480p" => (854, 480),
-                "720p" => (1280, 720),
-                _ => (640, 360),
-            };
-            settings.width = width;
-            settings.height = height;
-        }
+        let (width, height) = resolution(args.resolution.as_deref());
+        settings.width = width;
+        settings.height = height;
     }
 
     if let Some(mode) = args.mode {
@@ -70,22 +104,179 @@ pub async fn run_embed(args: EmbedParams) -> anyhow::Result<()> {
         settings.threads = threads;
     }
 
-    match out_mode {
-        OutputMode::Binary => {
-            let bytes = ethcer::rip_bytes(&args.in_path.expect("No path provided in arguments"))?;
+    if let Some(bits) = args.bits_per_channel {
+        settings.bits = bits;
+    }
 
-            let binary = ethcer::rip_binary(bytes)?;
+    if let Some(fec) = args.fec {
+        settings.fec = fec;
+    }
 
-            let data = Data::from_binary(binary);
+    if let Some(interval) = args.header_interval {
+        settings.header_interval = interval;
+    }
 
-            ethcer::etch("output.avi", data, settings)?;
-        }
-        OutputMode::Color => {
-            let bytes = ethcer::rip_bytes(&args.in_path.expect("No path provided in arguments"))?;
+    if let Some(depth) = args.interleave_depth {
+        settings.interleave_depth = depth;
+    }
+
+    if let Some(region) = &args.region {
+        settings.region = parse_region(region)?;
+    }
+
+    if matches!(out_mode, OutputMode::YCbCr) && settings.region[2] > 0 {
+        // Chroma cells have to start on even pixels to match 4:2:0 subsampling
+        let [x, y, width, height] = settings.region;
+        settings.region = [
+            x + x % 2,
+            y + y % 2,
+            (width - x % 2).max(1),
+            (height - y % 2).max(1),
+        ];
+    }
+
+    if args.overlay_image.is_some() {
+        settings.overlay_image = args.overlay_image.clone();
+    }
+
+    if args.overlay_text.is_some() {
+        settings.overlay_text = args.overlay_text.clone();
+    }
+
+    if settings.bits == 0 {
+        settings.bits = 2;
+    }
+
+    if settings.fec == 0 {
+        settings.fec = 1;
+    }
 
-            let data = Data::from_color(bytes);
+    if settings.header_interval == 0 {
+        settings.header_interval = 50;
+    }
+
+    settings.seed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .subsec_nanos();
+
+    let format = args.format.unwrap_or(OutputFormat::Video);
+    let in_path = args.in_path.expect("No path provided in arguments");
+    let bytes = ethcer::rip_bytes(&in_path)?;
+
+    if settings.size < 1 {
+        return Err(anyhow::anyhow!("Block size must be at least 1 pixel"));
+    }
+
+    if settings.fec % 2 == 0 {
+        return Err(anyhow::anyhow!(
+            "FEC ratio must be odd so the majority vote cannot tie"
+        ));
+    }
+
+    if !(1..=3).contains(&settings.bits) {
+        return Err(anyhow::anyhow!("Bits per channel must be between 1 and 3"));
+    }
+
+    if matches!(out_mode, OutputMode::Dct) && (settings.size < 8 || settings.size % 8 != 0) {
+        return Err(anyhow::anyhow!(
+            "DCT mode needs a block size that is a multiple of 8"
+        ));
+    }
+
+    if matches!(out_mode, OutputMode::Grayscale) && settings.bits < 2 {
+        return Err(anyhow::anyhow!(
+            "Grayscale mode needs 2 or 3 bits per block, use binary mode for 1"
+        ));
+    }
+
+    let gif_bits = match out_mode {
+        OutputMode::Binary | OutputMode::Ldpc => 1,
+        OutputMode::Grayscale if settings.bits == 2 => 2,
+        _ => 0,
+    };
+
+    if matches!(format, OutputFormat::Gif) && gif_bits == 0 {
+        return Err(anyhow::anyhow!(
+            "GIF output only supports binary, ldpc or 2-bit grayscale mode"
+        ));
+    }
+
+    if (args.audio || args.subtitles) && !matches!(format, OutputFormat::Video) {
+        return Err(anyhow::anyhow!(
+            "Audio and subtitle tracks need video output"
+        ));
+    }
 
-            ethcer::etch("output.avi", data, settings)?;
+    if (args.audio || args.subtitles) && matches!(out_mode, OutputMode::Qr) {
+        return Err(anyhow::anyhow!(
+            "QR mode has no header to put in an audio or subtitle track"
+        ));
+    }
+
+    if matches!(format, OutputFormat::Image) && args.resolution.is_none() {
+        let (width, height) = still::fit(&out_mode, bytes.len(), &settings);
+        settings.width = width;
+        settings.height = height;
+    }
+
+    let region = registration::embed_area(settings.width, settings.height, settings.region);
+    if region.width < settings.size * 2 || region.height < settings.size * 2 {
+        return Err(anyhow::anyhow!(
+            "Data region must lie inside the data area and fit at least 2x2 blocks"
+        ));
+    }
+
+    let card = TitleCard {
+        name: Path::new(&in_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| in_path.clone()),
+        bytes: bytes.len(),
+    };
+    let data = ethcer::prepare_data(bytes, out_mode, &settings)?;
+
+    let manifest = titlecard::manifest(&card, &data, &settings);
+    let title_card = args.title_card.then_some(card);
+
+    match format {
+        OutputFormat::Video => {
+            let words = ethcer::instruction_words(&settings, &data);
+            let fps = settings.fps;
+
+            let mut out_path = "output.avi".to_string();
+            ethcer::etch(&out_path, data, settings, title_card)?;
+
+            if args.audio {
+                audio::mux(&out_path, &words, fps)?;
+            }
+
+            if args.subtitles {
+                out_path = subtitles::mux(&out_path, &words, &manifest, fps)?;
+            }
+
+            if args.audio || args.subtitles {
+                println!("Final video written to {}", out_path);
+            }
+        }
+        OutputFormat::Paper => {
+            let frames = ethcer::render_frames(&data, &settings, title_card)?;
+            paper::write_pdf("output.pdf", &frames)?;
+        }
+        OutputFormat::PaperPng => {
+            let frames = ethcer::render_frames(&data, &settings, title_card)?;
+            paper::write_pngs("output_pages", &frames)?;
+        }
+        OutputFormat::Image => {
+            let frames = ethcer::render_frames(&data, &settings, title_card)?;
+            still::write("output.png", &frames)?;
+        }
+        OutputFormat::Gif => {
+            let frames = ethcer::render_frames(&data, &settings, title_card)?;
+            gif::write("output.gif", &frames, gif_bits, settings.fps)?;
+        }
+        OutputFormat::Webp => {
+            let frames = ethcer::render_frames(&data, &settings, title_card)?;
+            webp::write("output.webp", &frames, settings.fps)?;
         }
     }
 
@@ -0,0 +1,87 @@
+use anyhow::anyhow;
+
+use opencv::prelude::*;
+use opencv::videoio::{
+    CAP_ANY, CAP_PROP_FPS, CAP_PROP_FRAME_HEIGHT, CAP_PROP_FRAME_WIDTH, VideoCapture,
+};
+
+use crate::{
+    args::HideParams,
+    ethcer,
+    stego::{self, Carrier},
+};
+
+// The container frame count is often an estimate, so decode the cover once to count
+fn count_frames(path: &str) -> anyhow::Result<usize> {
+    let mut video = VideoCapture::from_file(path, CAP_ANY)?;
+    let mut frames = 0;
+
+    while video.grab()? {
+        frames += 1;
+    }
+
+    Ok(frames)
+}
+
+pub async fn run_hide(args: HideParams) -> anyhow::Result<()> {
+    let in_path = args.in_path.expect("No path provided in arguments");
+    let cover_path = args
+        .cover_path
+        .expect("No cover video provided in arguments");
+    let key = args.key.expect("No key provided in arguments");
+
+    let (carrier, out_path) = if args.lossy {
+        (Carrier::Dct, "output.mp4")
+    } else {
+        (Carrier::Lsb, "output.avi")
+    };
+
+    let video = VideoCapture::from_file(&cover_path, CAP_ANY)?;
+    if !video.is_opened()? {
+        return Err(anyhow!("Could not open cover video {}", cover_path));
+    }
+
+    let fps = match video.get(CAP_PROP_FPS)? {
+        fps if fps > 0.0 => fps,
+        _ => 30.0,
+    };
+    let frames = count_frames(&cover_path)?;
+    let width = video.get(CAP_PROP_FRAME_WIDTH)? as usize;
+    let height = video.get(CAP_PROP_FRAME_HEIGHT)? as usize;
+
+    let capacity = stego::capacity(frames, width, height, &carrier);
+    println!("Cover capacity: {} bytes in {} frames", capacity, frames);
+
+    let bytes = ethcer::rip_bytes(&in_path)?;
+
+    if bytes.len() > capacity {
+        return Err(anyhow!(
+            "Payload of {} bytes does not fit, the cover holds {} bytes",
+            bytes.len(),
+            capacity
+        ));
+    }
+
+    let psnr = stego::hide(
+        ethcer::video_frames(video),
+        &bytes,
+        &key,
+        &carrier,
+        out_path,
+        fps,
+    )?;
+    println!(
+        "Hid {} bytes ({:.1}% of capacity), PSNR against the cover before encoding: {:.2} dB",
+        bytes.len(),
+        bytes.len() as f64 / capacity.max(1) as f64 * 100.0,
+        psnr
+    );
+
+    let psnr = stego::verify(out_path, &cover_path, &bytes, &key)?;
+    println!(
+        "Self-check passed, {} gives back the payload, PSNR against the cover: {:.2} dB",
+        out_path, psnr
+    );
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use opencv::videoio::{CAP_ANY, VideoCapture};
+
+use crate::{args::InspectParams, ethcer, subtitles, titlecard};
+
+const SCAN_FRAMES: usize = 10;
+
+pub async fn run_inspect(args: InspectParams) -> anyhow::Result<()> {
+    let in_path = args
+        .in_path
+        .expect("Input path was not provided by the user");
+
+    let video = VideoCapture::from_file(&in_path, CAP_ANY)?;
+
+    for frame in ethcer::video_frames(video).take(SCAN_FRAMES) {
+        if let Some(text) = titlecard::read(&frame)? {
+            println!("Title card of {}:", in_path);
+
+            for line in text.lines() {
+                if let Some((key, value)) = line.split_once('=') {
+                    println!("  {:<16} {}", key, value);
+                }
+            }
+
+            return Ok(());
+        }
+    }
+
+    if subtitles::read(&in_path).manifest.is_some() {
+        return Ok(());
+    }
+
+    println!(
+        "No title card found in the first {} frames of {}",
+        SCAN_FRAMES, in_path
+    );
+    Ok(())
+}
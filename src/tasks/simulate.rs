@@ -0,0 +1,40 @@
+use opencv::videoio::{CAP_ANY, VideoCapture};
+
+use crate::{args::SimulateParams, ethcer, simulate};
+
+pub async fn run_simulate(args: SimulateParams) -> anyhow::Result<()> {
+    let in_path = args
+        .in_path
+        .expect("Input path was not provided by the user");
+    let profile = args.profile.expect("Profile was not provided by the user");
+
+    let degradations = simulate::parse_profile(&profile)?;
+
+    println!("Decoding the untouched video as reference");
+    let reference = ethcer::read(&in_path, 1, args.capture)?;
+
+    println!("Decoding the video under '{}'", profile);
+    let video = VideoCapture::from_file(&in_path, CAP_ANY)?;
+    let report = simulate::evaluate(
+        ethcer::video_frames(video),
+        &reference,
+        &degradations,
+        args.seed.unwrap_or(1337),
+        args.capture,
+    );
+
+    println!(
+        "Bit error rate: {:.6} ({} of {} bits)",
+        report.bit_error_rate(),
+        report.errors,
+        report.total
+    );
+
+    if report.recovered {
+        println!("Recovery succeeded");
+    } else {
+        println!("Recovery failed");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use opencv::core::{CV_8UC3, Mat, Point, Rect, Scalar, Size};
+use opencv::imgproc;
+use opencv::objdetect::{QRCodeDetector, QRCodeEncoder};
+use opencv::prelude::*;
+
+use crate::ethcer;
+use crate::registration;
+use crate::settings::{Data, Settings};
+
+pub const FORMAT_VERSION: u32 = 1;
+
+pub struct TitleCard {
+    pub name: String,
+
+    pub bytes: usize,
+}
+
+const MIN_TEXT_SCALE: f64 = 0.35;
+
+fn summary(card: &TitleCard, data: &Data, settings: &Settings) -> Vec<(&'static str, String)> {
+    let (frames, final_bits) = ethcer::data_layout(data, settings);
+    let region = registration::embed_area(settings.width, settings.height, settings.region);
+
+    vec![
+        ("format", format!("vidvault/{}", FORMAT_VERSION)),
+        ("name", card.name.clone()),
+        ("bytes", card.bytes.to_string()),
+        ("mode", format!("{:?}", data.out_mode)),
+        ("block_size", settings.size.to_string()),
+        ("bits", settings.bits.to_string()),
+        ("fec", settings.fec.to_string()),
+        (
+            "resolution",
+            format!("{}x{}", settings.width, settings.height),
+        ),
+        ("fps", settings.fps.to_string()),
+        ("header_interval", settings.header_interval.to_string()),
+        ("seed", settings.seed.to_string()),
+        (
+            "interleave_depth",
+            ethcer::interleave_depth(&data.out_mode, settings, data.length).to_string(),
+        ),
+        (
+            "region",
+            format!(
+                "{},{},{},{}",
+                region.x, region.y, region.width, region.height
+            ),
+        ),
+        ("data_frames", frames.to_string()),
+        ("final_frame_bits", final_bits.to_string()),
+    ]
+}
+
+pub fn manifest(card: &TitleCard, data: &Data, settings: &Settings) -> String {
+    summary(card, data, settings)
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn render(card: &TitleCard, data: &Data, settings: &Settings) -> anyhow::Result<Mat> {
+    let width = settings.width;
+    let height = settings.height;
+
+    let mut canvas = Mat::new_rows_cols_with_default(height, width, CV_8UC3, Scalar::all(255.0))?;
+
+    let text = manifest(card, data, settings);
+
+    let mut code = Mat::default();
+    QRCodeEncoder::create_def()?.encode(&text, &mut code)?;
+
+    let side = height * 2 / 3;
+    let mut scaled = Mat::default();
+    imgproc::resize(
+        &code,
+        &mut scaled,
+        Size::new(side, side),
+        0.0,
+        0.0,
+        imgproc::INTER_NEAREST,
+    )?;
+
+    let mut colored = Mat::default();
+    imgproc::cvt_color_def(&scaled, &mut colored, imgproc::COLOR_GRAY2BGR)?;
+
+    let qr_area = Rect::new(width - side - height / 6, (height - side) / 2, side, side);
+
+    let lines = [
+        "VidVault".to_string(),
+        format!("Format version {}", FORMAT_VERSION),
+        format!("Payload: {} bytes", card.bytes),
+        format!("File: {}", card.name),
+        "Recover with: vidvault dislodge".to_string(),
+    ];
+
+    let scale = (height as f64 / 900.0).max(MIN_TEXT_SCALE);
+    let line_height = (height / 10).max(12);
+    let thickness = (height / 360).max(1);
+
+    let max_width = qr_area.x - height / 12;
+
+    for (i, line) in lines.iter().enumerate() {
+        let line_scale = if i == 0 { scale * 2.0 } else { scale };
+        let line = fit_text(line, line_scale, thickness, max_width)?;
+
+        imgproc::put_text(
+            &mut canvas,
+            &line,
+            Point::new(height / 12, height / 4 + i as i32 * line_height),
+            imgproc::FONT_HERSHEY_SIMPLEX,
+            line_scale,
+            Scalar::all(0.0),
+            thickness,
+            imgproc::LINE_AA,
+            false,
+        )?;
+    }
+
+    {
+        let mut area = Mat::roi_mut(&mut canvas, qr_area)?;
+        colored.copy_to(&mut area)?;
+    }
+
+    Ok(canvas)
+}
+
+fn fit_text(line: &str, scale: f64, thickness: i32, max_width: i32) -> anyhow::Result<String> {
+    let width = |text: &str| -> anyhow::Result<i32> {
+        let mut baseline = 0;
+        let size = imgproc::get_text_size(
+            text,
+            imgproc::FONT_HERSHEY_SIMPLEX,
+            scale,
+            thickness,
+            &mut baseline,
+        )?;
+        Ok(size.width)
+    };
+
+    if width(line)? <= max_width {
+        return Ok(line.to_string());
+    }
+
+    let mut chars: Vec<char> = line.chars().collect();
+    while !chars.is_empty() {
+        chars.pop();
+        let shortened = format!("{}...", chars.iter().collect::<String>());
+        if width(&shortened)? <= max_width {
+            return Ok(shortened);
+        }
+    }
+
+    Ok(String::new())
+}
+
+pub fn read(frame: &Mat) -> anyhow::Result<Option<String>> {
+    let detector = QRCodeDetector::default()?;
+    let text = detector.detect_and_decode_def(frame)?;
+
+    if text.is_empty() {
+        return Ok(None);
+    }
+
+    let text = String::from_utf8_lossy(&text).to_string();
+
+    if !text.starts_with("format=vidvault/") {
+        return Ok(None);
+    }
+
+    Ok(Some(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_file_name_leaves_code_readable() {
+        let card = TitleCard {
+            name: format!(
+                "{}.tar.gz",
+                "quarterly-backup-of-the-shared-drive-".repeat(3)
+            ),
+            bytes: 123_456,
+        };
+        let data = Data::from_binary(vec![false; 4096]);
+
+        for (width, height) in [(640, 360), (1280, 720)] {
+            let mut settings = Settings::new(8, 1, 30, width, height);
+            settings.bits = 1;
+
+            let frame = render(&card, &data, &settings).unwrap();
+            let text = read(&frame).unwrap().unwrap();
+
+            assert_eq!(text, manifest(&card, &data, &settings));
+        }
+    }
+}
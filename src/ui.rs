@@ -4,7 +4,10 @@ use anyhow::{self, Ok};
 
 use inquire::{CustomType, Select, Text};
 
-use crate::args::{Commands, DislodgeParams, DownloadParams, EmbedParams};
+use crate::args::{
+    AutotuneParams, Commands, DislodgeParams, DownloadParams, EmbedParams, HideParams,
+    InspectParams, SimulateParams,
+};
 
 pub async fn enrich_arguments(args: Option<Commands>) -> anyhow::Result<Commands> {
     Ok(match args {
@@ -17,11 +20,23 @@ pub async fn enrich_arguments(args: Option<Commands>) -> anyhow::Result<Commands
         Some(Commands::Download(download_args)) => {
             Commands::Download(enrich_download_params(download_args).await?)
         }
+        Some(Commands::Simulate(simulate_args)) => {
+            Commands::Simulate(enrich_simulate_params(simulate_args).await?)
+        }
+        Some(Commands::Autotune(autotune_args)) => {
+            Commands::Autotune(enrich_autotune_params(autotune_args).await?)
+        }
+        Some(Commands::Inspect(inspect_args)) => {
+            Commands::Inspect(enrich_inspect_params(inspect_args).await?)
+        }
+        Some(Commands::Hide(hide_args)) => Commands::Hide(enrich_hide_params(hide_args).await?),
         None => {
-            let options = vec!["Embed", "Dislodge", "Download"];
+            let options = vec![
+                "Embed", "Dislodge", "Download", "Simulate", "Autotune", "Inspect", "Hide",
+            ];
 
             let modes = Select::new("Pick what what you want to do with the program", options)
-                .with_help_message("Embed: Create a video from files\nDownload: Dowload Files from Youtube\nDislodge: Return Files from an embedded video")
+                .with_help_message("Embed: Create a video from files\nDownload: Dowload Files from Youtube\nDislodge: Return Files from an embedded video\nSimulate: Test an embedded video against compression\nAutotune: Find the densest settings that survive compression\nInspect: Read the title card of an embedded video\nHide: Hide files inside an existing video")
                 .prompt()
                 .unwrap();
 
@@ -33,6 +48,16 @@ pub async fn enrich_arguments(args: Option<Commands>) -> anyhow::Result<Commands
                 "Download" => {
                     Commands::Download(enrich_download_params(DownloadParams::default()).await?)
                 }
+                "Simulate" => {
+                    Commands::Simulate(enrich_simulate_params(SimulateParams::default()).await?)
+                }
+                "Autotune" => {
+                    Commands::Autotune(enrich_autotune_params(AutotuneParams::default()).await?)
+                }
+                "Inspect" => {
+                    Commands::Inspect(enrich_inspect_params(InspectParams::default()).await?)
+                }
+                "Hide" => Commands::Hide(enrich_hide_params(HideParams::default()).await?),
                 _ => unreachable!(),
             }
         }
@@ -49,16 +74,25 @@ async fn enrich_embed_params(mut args: EmbedParams) -> anyhow::Result<EmbedParam
         args.in_path = Some(path);
     }
 
+    if args.saved_preset.is_some() {
+        return Ok(args);
+    }
+
     if args.mode.is_none()
         && args.block_size.is_none()
         && args.threads.is_none()
         && args.fps.is_none()
         && args.resolution.is_none()
+        && args.bits_per_channel.is_none()
+        && args.fec.is_none()
+        && args.header_interval.is_none()
+        && args.interleave_depth.is_none()
     {
         let presets = vec![
             "Optimal Compression Resistance",
             "Paranoid Compression Resistance",
             "Maximum Efficiency",
+            "Grayscale Multi-Level",
             "Custom",
         ];
 
@@ -85,12 +119,25 @@ async fn enrich_embed_params(mut args: EmbedParams) -> anyhow::Result<EmbedParam
                 args.preset = Some(crate::args::EmbedPreset::Paranoid);
                 return Ok(args);
             }
+            "Grayscale Multi-Level" => {
+                args.preset = Some(crate::args::EmbedPreset::Grayscale);
+                return Ok(args);
+            }
             _ => (),
         }
     }
 
     if args.mode.is_none() {
-        let out_modes = vec!["Colored", "B/W (Binary)"];
+        let out_modes = vec![
+            "Colored",
+            "Quantized Color",
+            "Grayscale (Multi-Level)",
+            "YCbCr (4:2:0 Aware)",
+            "DCT (Macroblock Aligned)",
+            "LDPC (Soft Decision)",
+            "QR Code (Phone Scannable)",
+            "B/W (Binary)",
+        ];
         let out_mode = Select::new("Pick the mode of data embedding", out_modes.clone())
             .with_help_message("Colored mod is useless if video undergoes compression at any point, B/W is the best option")
             .prompt()
@@ -98,11 +145,37 @@ async fn enrich_embed_params(mut args: EmbedParams) -> anyhow::Result<EmbedParam
 
         args.mode = Some(match out_mode {
             "Colored" => crate::args::EmbedOutputMode::Colored,
+            "Quantized Color" => crate::args::EmbedOutputMode::Quantized,
+            "Grayscale (Multi-Level)" => crate::args::EmbedOutputMode::Grayscale,
+            "YCbCr (4:2:0 Aware)" => crate::args::EmbedOutputMode::YCbCr,
+            "DCT (Macroblock Aligned)" => crate::args::EmbedOutputMode::Dct,
+            "LDPC (Soft Decision)" => crate::args::EmbedOutputMode::Ldpc,
+            "QR Code (Phone Scannable)" => crate::args::EmbedOutputMode::Qr,
             "B/W (Binary)" => crate::args::EmbedOutputMode::Binary,
             _ => unreachable!(),
         });
     }
 
+    if matches!(
+        args.mode,
+        Some(
+            crate::args::EmbedOutputMode::Quantized
+                | crate::args::EmbedOutputMode::Grayscale
+                | crate::args::EmbedOutputMode::YCbCr
+        )
+    ) && args.bits_per_channel.is_none()
+    {
+        let bits = CustomType::<u8>::new("How many bits should each channel carry ?")
+            .with_error_message("Please enter a valid number")
+            .with_help_message(
+                "Fewer bits survive more compression, 1-3 is allowed, 2 is recommended",
+            )
+            .with_default(2)
+            .prompt()?;
+
+        args.bits_per_channel = Some(bits);
+    }
+
     if args.block_size.is_none() {
         let size = CustomType::<i32>::new("What should be size of the blocks ?")
             .with_error_message("Please enter a valid number")
@@ -183,3 +256,92 @@ async fn enrich_dislodge_params(mut args: DislodgeParams) -> anyhow::Result<Disl
 
     Ok(args)
 }
+
+async fn enrich_simulate_params(mut args: SimulateParams) -> anyhow::Result<SimulateParams> {
+    if args.in_path.is_none() {
+        let in_path = Text::new("Enter the path to your video file")
+            .with_default("output.avi")
+            .prompt()
+            .unwrap();
+        args.in_path = Some(in_path);
+    }
+
+    if args.profile.is_none() {
+        let profile = Text::new("Enter the degradation profile")
+            .with_help_message("Comma separated steps: jpeg=Q, blur=SIGMA, noise=SIGMA, scale=FACTOR, drop=P, duplicate=P, chroma")
+            .with_default("jpeg=60,scale=0.75")
+            .prompt()
+            .unwrap();
+        args.profile = Some(profile);
+    }
+
+    Ok(args)
+}
+
+async fn enrich_autotune_params(mut args: AutotuneParams) -> anyhow::Result<AutotuneParams> {
+    if args.profile.is_none() {
+        let profile = Text::new("Enter the degradation profile to tune against")
+            .with_help_message("Comma separated steps: jpeg=Q, blur=SIGMA, noise=SIGMA, scale=FACTOR, drop=P, duplicate=P, chroma")
+            .with_default("jpeg=60,scale=0.75")
+            .prompt()
+            .unwrap();
+        args.profile = Some(profile);
+    }
+
+    if args.resolution.is_none() {
+        let resolutions = vec!["144p", "240p", "360p", "480p", "720p"];
+        let resolution = Select::new("Pick the resolution to tune for", resolutions)
+            .prompt()
+            .unwrap();
+        args.resolution = Some(resolution.to_string());
+    }
+
+    if args.name.is_none() {
+        let name = Text::new("Enter a name for the tuned preset")
+            .with_help_message("Embed with it later using --saved-preset NAME")
+            .with_default("tuned")
+            .prompt()
+            .unwrap();
+        args.name = Some(name);
+    }
+
+    Ok(args)
+}
+
+async fn enrich_inspect_params(mut args: InspectParams) -> anyhow::Result<InspectParams> {
+    if args.in_path.is_none() {
+        let in_path = Text::new("Enter the path to your video file")
+            .with_default("output.avi")
+            .prompt()
+            .unwrap();
+        args.in_path = Some(in_path);
+    }
+
+    Ok(args)
+}
+
+async fn enrich_hide_params(mut args: HideParams) -> anyhow::Result<HideParams> {
+    if args.in_path.is_none() {
+        let in_path = Text::new("Enter the path to the file you want to hide")
+            .prompt()
+            .unwrap();
+        args.in_path = Some(in_path);
+    }
+
+    if args.cover_path.is_none() {
+        let cover_path = Text::new("Enter the path to the cover video")
+            .prompt()
+            .unwrap();
+        args.cover_path = Some(cover_path);
+    }
+
+    if args.key.is_none() {
+        let key = Text::new("Enter the key")
+            .with_help_message("The same key is needed to dislodge the data")
+            .prompt()
+            .unwrap();
+        args.key = Some(key);
+    }
+
+    Ok(args)
+}
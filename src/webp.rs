@@ -0,0 +1,285 @@
+use std::fs;
+
+use anyhow::anyhow;
+
+use opencv::core::{CV_8UC3, Mat, Rect, Scalar, Vector};
+use opencv::imgcodecs;
+use opencv::prelude::*;
+
+const LOSSLESS_QUALITY: i32 = 101;
+
+const ANIMATION_FLAG: u8 = 0x02;
+
+const NO_BLEND_FLAG: u8 = 0x02;
+
+fn put_u24(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes()[..3]);
+}
+
+fn get_u24(bytes: &[u8]) -> u32 {
+    bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16
+}
+
+fn put_chunk(out: &mut Vec<u8>, tag: &[u8; 4], payload: &[u8]) {
+    out.extend_from_slice(tag);
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(payload);
+
+    if payload.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+fn chunks(bytes: &[u8]) -> anyhow::Result<Vec<([u8; 4], &[u8])>> {
+    let mut chunks = Vec::new();
+    let mut offset = 0;
+
+    while offset + 8 <= bytes.len() {
+        let tag = [
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ];
+        let size = u32::from_le_bytes([
+            bytes[offset + 4],
+            bytes[offset + 5],
+            bytes[offset + 6],
+            bytes[offset + 7],
+        ]) as usize;
+
+        let payload = bytes
+            .get(offset + 8..offset + 8 + size)
+            .ok_or_else(|| anyhow!("Truncated WebP chunk"))?;
+
+        chunks.push((tag, payload));
+        offset += 8 + size + size % 2;
+    }
+
+    Ok(chunks)
+}
+
+fn riff_body(bytes: &[u8]) -> anyhow::Result<&[u8]> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WEBP" {
+        return Err(anyhow!("Not a WebP file"));
+    }
+
+    Ok(&bytes[12..])
+}
+
+fn encode_frame(frame: &Mat) -> anyhow::Result<Vec<u8>> {
+    let params = Vector::from_slice(&[imgcodecs::IMWRITE_WEBP_QUALITY, LOSSLESS_QUALITY]);
+    let mut buffer = Vector::<u8>::new();
+    imgcodecs::imencode(".webp", frame, &mut buffer, &params)?;
+
+    let encoded = buffer.to_vec();
+    let mut data = Vec::new();
+
+    for (tag, payload) in chunks(riff_body(&encoded)?)? {
+        if matches!(&tag, b"ALPH" | b"VP8 " | b"VP8L") {
+            put_chunk(&mut data, &tag, payload);
+        }
+    }
+
+    Ok(data)
+}
+
+fn decode_frame(data: &[u8]) -> anyhow::Result<Mat> {
+    let mut riff = Vec::with_capacity(data.len() + 12);
+    riff.extend_from_slice(b"RIFF");
+    riff.extend_from_slice(&(data.len() as u32 + 4).to_le_bytes());
+    riff.extend_from_slice(b"WEBP");
+    riff.extend_from_slice(data);
+
+    let frame = imgcodecs::imdecode(&Vector::<u8>::from_slice(&riff), imgcodecs::IMREAD_COLOR)?;
+
+    if frame.empty() {
+        return Err(anyhow!("Failed to decode WebP frame"));
+    }
+
+    Ok(frame)
+}
+
+fn container(width: u32, height: u32, duration: u32, frames: &[Vec<u8>]) -> Vec<u8> {
+    let mut body = b"WEBP".to_vec();
+
+    let mut vp8x = vec![ANIMATION_FLAG, 0, 0, 0];
+    put_u24(&mut vp8x, width - 1);
+    put_u24(&mut vp8x, height - 1);
+    put_chunk(&mut body, b"VP8X", &vp8x);
+
+    put_chunk(&mut body, b"ANIM", &[0, 0, 0, 0, 0, 0]);
+
+    for frame in frames {
+        let mut anmf = Vec::new();
+        put_u24(&mut anmf, 0);
+        put_u24(&mut anmf, 0);
+        put_u24(&mut anmf, width - 1);
+        put_u24(&mut anmf, height - 1);
+        put_u24(&mut anmf, duration);
+        anmf.push(NO_BLEND_FLAG);
+        anmf.extend_from_slice(frame);
+
+        put_chunk(&mut body, b"ANMF", &anmf);
+    }
+
+    let mut webp = b"RIFF".to_vec();
+    webp.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    webp.extend_from_slice(&body);
+    webp
+}
+
+type Animation<'a> = ((i32, i32), Vec<(i32, i32, &'a [u8])>);
+
+fn animation(bytes: &[u8]) -> anyhow::Result<Option<Animation<'_>>> {
+    let chunks = chunks(riff_body(bytes)?)?;
+
+    let vp8x = chunks.iter().find(|(tag, _)| tag == b"VP8X");
+    let animated = chunks.iter().any(|(tag, _)| tag == b"ANMF");
+
+    let size = match vp8x {
+        Some((_, payload)) if animated && payload.len() >= 10 => (
+            get_u24(&payload[4..7]) as i32 + 1,
+            get_u24(&payload[7..10]) as i32 + 1,
+        ),
+        _ => return Ok(None),
+    };
+
+    let mut frames = Vec::new();
+
+    for (_, payload) in chunks.iter().filter(|(tag, _)| tag == b"ANMF") {
+        if payload.len() < 16 {
+            return Err(anyhow!("Truncated WebP animation frame"));
+        }
+
+        frames.push((
+            get_u24(&payload[0..3]) as i32 * 2,
+            get_u24(&payload[3..6]) as i32 * 2,
+            &payload[16..],
+        ));
+    }
+
+    Ok(Some((size, frames)))
+}
+
+pub fn write(path: &str, frames: &[Mat], fps: f64) -> anyhow::Result<()> {
+    let first = frames
+        .first()
+        .ok_or_else(|| anyhow!("No frames to write"))?;
+    let duration = (1000.0 / fps.max(1.0)).round() as u32;
+
+    let encoded = frames
+        .iter()
+        .map(encode_frame)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let webp = container(first.cols() as u32, first.rows() as u32, duration, &encoded);
+
+    fs::write(path, webp)?;
+    println!("{} frames written to {}", frames.len(), path);
+    Ok(())
+}
+
+pub fn read(path: &str) -> anyhow::Result<Vec<Mat>> {
+    let bytes = fs::read(path)?;
+
+    let Some(((width, height), animation)) = animation(&bytes)? else {
+        let frame =
+            imgcodecs::imdecode(&Vector::<u8>::from_slice(&bytes), imgcodecs::IMREAD_COLOR)?;
+
+        if frame.empty() {
+            return Err(anyhow!("Failed to read image {}", path));
+        }
+
+        return Ok(vec![frame]);
+    };
+
+    let mut canvas = Mat::new_rows_cols_with_default(height, width, CV_8UC3, Scalar::all(0.0))?;
+    let mut frames = Vec::new();
+
+    for (x, y, data) in animation {
+        let frame = decode_frame(data)?;
+
+        let rect = Rect::new(
+            x,
+            y,
+            frame.cols().min(width - x),
+            frame.rows().min(height - y),
+        );
+
+        if rect.width > 0 && rect.height > 0 {
+            let source = Mat::roi(&frame, Rect::new(0, 0, rect.width, rect.height))?;
+            let mut target = Mat::roi_mut(&mut canvas, rect)?;
+            source.copy_to(&mut target)?;
+        }
+
+        frames.push(canvas.try_clone()?);
+    }
+
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame_data(payload: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        put_chunk(&mut data, b"VP8L", payload);
+        data
+    }
+
+    #[test]
+    fn odd_chunks_are_padded() {
+        let data = frame_data(&[1, 2, 3, 4, 5]);
+
+        assert_eq!(data.len(), 8 + 6);
+        assert_eq!(&data[4..8], &5u32.to_le_bytes());
+        assert_eq!(
+            chunks(&data).unwrap(),
+            vec![(*b"VP8L", &[1, 2, 3, 4, 5][..])]
+        );
+    }
+
+    #[test]
+    fn container_round_trips_frames() {
+        let frames = vec![
+            frame_data(&[1, 2, 3]),
+            frame_data(&[4, 5, 6, 7]),
+            frame_data(&[8]),
+        ];
+        let webp = container(640, 360, 33, &frames);
+
+        assert_eq!(&webp[0..4], b"RIFF");
+        assert_eq!(
+            u32::from_le_bytes([webp[4], webp[5], webp[6], webp[7]]) as usize,
+            webp.len() - 8
+        );
+
+        let tags: Vec<[u8; 4]> = chunks(riff_body(&webp).unwrap())
+            .unwrap()
+            .iter()
+            .map(|(tag, _)| *tag)
+            .collect();
+        assert_eq!(tags, [*b"VP8X", *b"ANIM", *b"ANMF", *b"ANMF", *b"ANMF"]);
+
+        let ((width, height), parsed) = animation(&webp).unwrap().unwrap();
+        assert_eq!((width, height), (640, 360));
+        assert_eq!(parsed.len(), frames.len());
+
+        for ((x, y, data), frame) in parsed.iter().zip(&frames) {
+            assert_eq!((*x, *y), (0, 0));
+            assert_eq!(*data, &frame[..]);
+        }
+    }
+
+    #[test]
+    fn still_and_truncated_files() {
+        let mut still = b"RIFF\0\0\0\0WEBP".to_vec();
+        still.extend(frame_data(&[1, 2, 3]));
+        assert!(animation(&still).unwrap().is_none());
+
+        let webp = container(16, 16, 100, &[frame_data(&[1, 2, 3])]);
+        assert!(animation(&webp[..webp.len() - 2]).is_err());
+        assert!(animation(b"RIFF\0\0\0\0AVI ").is_err());
+    }
+}
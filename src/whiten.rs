@@ -0,0 +1,32 @@
+use crate::prng::Rng;
+
+pub fn scramble(data: &[u8], seed: u64) -> Vec<u8> {
+    let mut rng = Rng::new(seed);
+
+    data.iter()
+        .map(|byte| byte ^ (rng.next_u64() >> 56) as u8)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scramble_is_an_involution() {
+        let data: Vec<u8> = (0..4096).map(|i| (i % 256) as u8).collect();
+
+        for seed in [0, 1, 0xDEAD_BEEF] {
+            assert_eq!(scramble(&scramble(&data, seed), seed), data);
+        }
+    }
+
+    #[test]
+    fn scramble_breaks_up_constant_runs() {
+        let scrambled = scramble(&[0u8; 4096], 42);
+        let ones: u32 = scrambled.iter().map(|byte| byte.count_ones()).sum();
+
+        assert!((ones as f64 / (4096.0 * 8.0) - 0.5).abs() < 0.02);
+        assert_ne!(scramble(&[0u8; 64], 1), scramble(&[0u8; 64], 2));
+    }
+}
@@ -0,0 +1,35 @@
+pub const LUMA_LOW: u8 = 72;
+
+pub const LUMA_HIGH: u8 = 184;
+
+pub const CHROMA_SWING: f64 = 36.0;
+
+pub fn to_rgb(luma: u8, cb: f64, cr: f64) -> Vec<u8> {
+    let luma = luma as f64;
+
+    let r = luma + 1.402 * (cr - 128.0);
+    let g = luma - 0.344136 * (cb - 128.0) - 0.714136 * (cr - 128.0);
+    let b = luma + 1.772 * (cb - 128.0);
+
+    vec![
+        r.round().clamp(0.0, 255.0) as u8,
+        g.round().clamp(0.0, 255.0) as u8,
+        b.round().clamp(0.0, 255.0) as u8,
+    ]
+}
+
+pub fn from_rgb(rgb: &[f64]) -> (f64, f64, f64) {
+    let luma = 0.299 * rgb[0] + 0.587 * rgb[1] + 0.114 * rgb[2];
+    let cb = 128.0 - 0.168736 * rgb[0] - 0.331264 * rgb[1] + 0.5 * rgb[2];
+    let cr = 128.0 + 0.5 * rgb[0] - 0.418688 * rgb[1] - 0.081312 * rgb[2];
+
+    (luma, cb, cr)
+}
+
+pub fn chroma(bit: bool) -> f64 {
+    if bit {
+        128.0 + CHROMA_SWING
+    } else {
+        128.0 - CHROMA_SWING
+    }
+}